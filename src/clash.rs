@@ -1,6 +1,8 @@
+mod constraints;
 mod public_handle;
 mod testcase;
 
+pub use constraints::{parse_constraints, Bound, Bounds, CharClass, VariableConstraints};
 pub use public_handle::PublicHandle;
 use serde::{Deserialize, Serialize};
 use testcase::deserialize_testcases;
@@ -22,8 +24,9 @@ pub struct Clash {
     downvotes: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-enum PuzzleType {
+/// Whether a puzzle is a Clash of Code or a classic I/O puzzle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PuzzleType {
     #[serde(rename = "CLASHOFCODE")]
     Clash,
     #[serde(rename = "PUZZLE_INOUT")]
@@ -70,6 +73,18 @@ impl Clash {
         &self.last_version.data.testcases
     }
 
+    pub fn puzzle_type(&self) -> PuzzleType {
+        self.puzzle_type
+    }
+
+    pub fn upvotes(&self) -> i32 {
+        self.upvotes
+    }
+
+    pub fn downvotes(&self) -> i32 {
+        self.downvotes
+    }
+
     pub fn codingame_link(&self) -> String {
         format!("https://www.codingame.com/contribute/view/{}", self.public_handle)
     }
@@ -86,6 +101,12 @@ impl Clash {
         self.last_version.data.constraints.as_deref()
     }
 
+    /// Bounds on the variables extracted from [Clash::constraints], see
+    /// [parse_constraints].
+    pub fn variable_constraints(&self) -> Vec<VariableConstraints> {
+        self.constraints().map(parse_constraints).unwrap_or_default()
+    }
+
     pub fn stub_generator(&self) -> Option<&str> {
         self.last_version.data.stub_generator.as_deref()
     }
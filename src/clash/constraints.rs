@@ -0,0 +1,807 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// Bounds on one variable extracted from the constraints of a clash.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VariableConstraints {
+    pub variable: String,
+    /// Bounds on the value of the variable.
+    pub value: Bounds,
+    /// Bounds on the length of the variable (if it is a word or a string).
+    pub length: Bounds,
+    /// The kinds of characters the variable consists of, empty if the
+    /// constraints don't say.
+    pub charset: Vec<CharClass>,
+}
+
+/// Lower and upper bound of a range, either may be missing.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Bounds {
+    pub min: Option<Bound>,
+    pub max: Option<Bound>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bound {
+    pub value: f64,
+    /// Whether the value itself is in range (`≤` rather than `<`).
+    pub inclusive: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CharClass {
+    Lowercase,
+    Uppercase,
+    Digits,
+    Spaces,
+    Punctuation,
+}
+
+impl Bounds {
+    pub fn is_empty(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+
+    pub fn contains(&self, value: f64) -> bool {
+        let above_min = self.min.map_or(true, |min| value > min.value || min.inclusive && value == min.value);
+        let below_max = self.max.map_or(true, |max| value < max.value || max.inclusive && value == max.value);
+        above_min && below_max
+    }
+}
+
+impl CharClass {
+    /// All the characters in the class.
+    pub fn chars(&self) -> Vec<char> {
+        match self {
+            CharClass::Lowercase => ('a'..='z').collect(),
+            CharClass::Uppercase => ('A'..='Z').collect(),
+            CharClass::Digits => ('0'..='9').collect(),
+            CharClass::Spaces => vec![' '],
+            CharClass::Punctuation => ('!'..='~').filter(char::is_ascii_punctuation).collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for Bounds {
+    /// Interval notation, for example `[1, 100]`, `(0, 1000)` or `[1, ∞)`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.min {
+            Some(Bound {
+                value,
+                inclusive: true,
+            }) => write!(f, "[{value}, ")?,
+            Some(Bound {
+                value,
+                inclusive: false,
+            }) => write!(f, "({value}, ")?,
+            None => write!(f, "(-∞, ")?,
+        }
+        match self.max {
+            Some(Bound {
+                value,
+                inclusive: true,
+            }) => write!(f, "{value}]"),
+            Some(Bound {
+                value,
+                inclusive: false,
+            }) => write!(f, "{value})"),
+            None => write!(f, "∞)"),
+        }
+    }
+}
+
+impl std::fmt::Display for CharClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CharClass::Lowercase => write!(f, "lowercase"),
+            CharClass::Uppercase => write!(f, "uppercase"),
+            CharClass::Digits => write!(f, "digits"),
+            CharClass::Spaces => write!(f, "spaces"),
+            CharClass::Punctuation => write!(f, "punctuation"),
+        }
+    }
+}
+
+lazy_static! {
+    static ref RE_MARKED_VARIABLE: Regex = Regex::new(r"\[\[\s*([A-Za-z_][A-Za-z0-9_]*)\s*\]\]").unwrap();
+    static ref RE_FORMATTING: Regex = Regex::new(r"\[\[|\]\]|\{\{|\}\}|<<|>>|`").unwrap();
+}
+
+/// Extract per-variable bounds from the constraints of a clash, for example
+/// `1 ≤ [[N]] ≤ 100`, `0 < {{L}} < 10^5`, `1 ≤ length of [[S]] ≤ 50` or
+/// `[[S]] contains only lowercase letters`. The constraints are free text, so
+/// anything that can't be understood is skipped. Variables are returned in
+/// the order they are first mentioned.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::clash::{parse_constraints, CharClass};
+///
+/// let constraints = parse_constraints("1 ≤ [[N]] ≤ 100\n[[S]] has lowercase letters only");
+/// assert_eq!(constraints[0].variable, "N");
+/// assert_eq!(constraints[0].value.to_string(), "[1, 100]");
+/// assert_eq!(constraints[1].charset, [CharClass::Lowercase]);
+/// ```
+pub fn parse_constraints(text: &str) -> Vec<VariableConstraints> {
+    let mut constraints: Vec<VariableConstraints> = Vec::new();
+    for line in text.lines().flat_map(|line| line.split(';')) {
+        let marked: Vec<String> =
+            RE_MARKED_VARIABLE.captures_iter(line).map(|caps| caps[1].to_string()).collect();
+        let tokens = tokenize(&normalize(line));
+        for (variable, update) in parse_line(&tokens, &marked) {
+            let index = match constraints.iter().position(|c| c.variable == variable) {
+                Some(index) => index,
+                None => {
+                    constraints.push(VariableConstraints {
+                        variable,
+                        ..Default::default()
+                    });
+                    constraints.len() - 1
+                }
+            };
+            update.apply(&mut constraints[index]);
+        }
+    }
+    constraints
+}
+
+// Replaces the different ways of writing operators and powers with one
+fn normalize(line: &str) -> String {
+    let mut line = RE_FORMATTING.replace_all(line, "").into_owned();
+    for (from, to) in [
+        ("<=", "≤"),
+        (">=", "≥"),
+        ("⩽", "≤"),
+        ("⩾", "≥"),
+        ("&lt;", "<"),
+        ("&gt;", ">"),
+        ("−", "-"),
+        ("–", "-"),
+        ("**", "^"),
+        ("×", "*"),
+        ("·", "*"),
+    ] {
+        line = line.replace(from, to);
+    }
+    let mut normalized = String::with_capacity(line.len());
+    let mut in_superscript = false;
+    for ch in line.chars() {
+        let digit = "⁰¹²³⁴⁵⁶⁷⁸⁹".chars().position(|superscript| superscript == ch);
+        match (ch, digit) {
+            ('⁻', _) | (_, Some(_)) if !in_superscript => {
+                in_superscript = true;
+                normalized.push('^');
+            }
+            _ => {}
+        }
+        match (ch, digit) {
+            ('⁻', _) => normalized.push('-'),
+            (_, Some(digit)) => normalized.push(char::from(b'0' + digit as u8)),
+            _ => {
+                in_superscript = false;
+                normalized.push(ch);
+            }
+        }
+    }
+    normalized
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Word(String),
+    Op(Op),
+    Punct(char),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+}
+
+fn tokenize(line: &str) -> Vec<Token> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let ch = chars[i];
+        let starts_number = |at: usize| {
+            chars.get(at).is_some_and(char::is_ascii_digit)
+                || chars.get(at) == Some(&'.') && chars.get(at + 1).is_some_and(char::is_ascii_digit)
+        };
+        let after_operand = matches!(tokens.last(), Some(Token::Number(_) | Token::Word(_)));
+        if starts_number(i) || ch == '-' && !after_operand && starts_number(i + 1) {
+            let (value, end) = number(&chars, i);
+            tokens.push(Token::Number(value));
+            i = end;
+            continue
+        }
+        if ch.is_alphabetic() || ch == '_' {
+            let end = (i..chars.len())
+                .find(|&j| !(chars[j].is_alphanumeric() || chars[j] == '_'))
+                .unwrap_or(chars.len());
+            tokens.push(Token::Word(chars[i..end].iter().collect()));
+            i = end;
+            continue
+        }
+        match ch {
+            '≤' => tokens.push(Token::Op(Op::LessEqual)),
+            '≥' => tokens.push(Token::Op(Op::GreaterEqual)),
+            '<' => tokens.push(Token::Op(Op::Less)),
+            '>' => tokens.push(Token::Op(Op::Greater)),
+            '=' => tokens.push(Token::Op(Op::Equal)),
+            _ if ch.is_whitespace() => {}
+            _ => tokens.push(Token::Punct(ch)),
+        }
+        i += 1;
+    }
+    tokens
+}
+
+// Numbers like 100, -2.5, 1e9, 10^5, 2*10^5 and -2^31
+fn number(chars: &[char], start: usize) -> (f64, usize) {
+    let skip_spaces = |mut at: usize| {
+        while chars.get(at) == Some(&' ') {
+            at += 1;
+        }
+        at
+    };
+    let literal = |at: usize| {
+        let negative = chars.get(at) == Some(&'-');
+        let mut end = at + usize::from(negative);
+        while chars.get(end).is_some_and(|ch| ch.is_ascii_digit() || *ch == '.') {
+            end += 1;
+        }
+        let digits_end = end;
+        // Scientific notation, but not the start of a word like "1 element"
+        if matches!(chars.get(end), Some('e' | 'E')) {
+            let mut exponent_end = end + 1;
+            if matches!(chars.get(exponent_end), Some('-' | '+')) {
+                exponent_end += 1;
+            }
+            let digits_start = exponent_end;
+            while chars.get(exponent_end).is_some_and(char::is_ascii_digit) {
+                exponent_end += 1;
+            }
+            let ends_word = !chars.get(exponent_end).is_some_and(|ch| ch.is_alphanumeric());
+            if exponent_end > digits_start && ends_word {
+                end = exponent_end;
+            }
+        }
+        let text: String = chars[at..end].iter().collect();
+        match text.parse::<f64>() {
+            Ok(value) => Some((value, end)),
+            Err(_) => chars[at..digits_end]
+                .iter()
+                .collect::<String>()
+                .parse()
+                .ok()
+                .map(|v| (v, digits_end)),
+        }
+    };
+
+    let negative = chars[start] == '-';
+    let Some((mut value, mut end)) = literal(start + usize::from(negative)) else {
+        return (f64::NAN, start + 1)
+    };
+    loop {
+        let next = skip_spaces(end);
+        match chars.get(next) {
+            Some('^') => match literal(skip_spaces(next + 1)) {
+                Some((exponent, exponent_end)) => {
+                    value = value.powf(exponent);
+                    end = exponent_end;
+                }
+                None => break,
+            },
+            Some('*') => match literal(skip_spaces(next + 1)) {
+                Some((factor, factor_end)) => {
+                    let next = skip_spaces(factor_end);
+                    // Powers bind tighter: 2*10^5
+                    if chars.get(next) == Some(&'^') {
+                        match literal(skip_spaces(next + 1)) {
+                            Some((exponent, exponent_end)) => {
+                                value *= factor.powf(exponent);
+                                end = exponent_end;
+                            }
+                            None => break,
+                        }
+                    } else {
+                        value *= factor;
+                        end = factor_end;
+                    }
+                }
+                None => break,
+            },
+            _ => break,
+        }
+    }
+    (if negative { -value } else { value }, end)
+}
+
+/// What a line says about one variable.
+#[derive(Debug, Default)]
+struct Update {
+    value: Bounds,
+    length: Bounds,
+    charset: Vec<CharClass>,
+}
+
+impl Update {
+    fn apply(self, constraints: &mut VariableConstraints) {
+        for (bounds, update) in [
+            (&mut constraints.value, self.value),
+            (&mut constraints.length, self.length),
+        ] {
+            bounds.min = update.min.or(bounds.min);
+            bounds.max = update.max.or(bounds.max);
+        }
+        for class in self.charset {
+            if !constraints.charset.contains(&class) {
+                constraints.charset.push(class);
+            }
+        }
+        constraints.charset.sort();
+    }
+}
+
+/// A variable or the length of a variable.
+#[derive(Debug, Clone)]
+struct Subject {
+    variable: String,
+    length: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    Number(f64),
+    Subjects(Vec<Subject>),
+}
+
+// Words that are never variables
+const KEYWORDS: [&str; 11] = [
+    "and", "or", "of", "is", "are", "the", "length", "len", "size", "between", "for",
+];
+
+// Units of the length of a string
+const CHARACTERS: [&str; 5] = ["characters", "character", "chars", "letters", "digits"];
+
+fn parse_line(tokens: &[Token], marked: &[String]) -> Vec<(String, Update)> {
+    let mut updates: Vec<(String, Update)> = Vec::new();
+    let mut compared = Vec::new();
+    let mut comparisons = Vec::new();
+
+    let mut i = 0;
+    while i < tokens.len() {
+        // Chains of comparisons: 1 ≤ N, M ≤ 100
+        if let Some((operands, ops, end)) = comparison_chain(tokens, i) {
+            for (k, op) in ops.into_iter().enumerate() {
+                match (&operands[k], &operands[k + 1]) {
+                    (Operand::Subjects(subjects), Operand::Number(number)) => {
+                        comparisons.extend(subjects.iter().map(|subject| (subject.clone(), op, *number)));
+                    }
+                    (Operand::Number(number), Operand::Subjects(subjects)) => {
+                        let op = op.flipped();
+                        comparisons.extend(subjects.iter().map(|subject| (subject.clone(), op, *number)));
+                    }
+                    _ => {}
+                }
+            }
+            i = end;
+            continue
+        }
+        // N is between 1 and 100
+        if let Some((subjects, mut end)) = subject_list(tokens, i) {
+            if word_at(tokens, end, &["is", "are"]) {
+                end += 1;
+            }
+            if let (true, Some(Token::Number(min)), true, Some(Token::Number(max))) = (
+                word_at(tokens, end, &["between"]),
+                tokens.get(end + 1),
+                word_at(tokens, end + 2, &["and"]),
+                tokens.get(end + 3),
+            ) {
+                // Unless it's the length: between 1 and 20 characters
+                if word_at(tokens, end + 4, &CHARACTERS) {
+                    i += 1;
+                    continue
+                }
+                for subject in subjects {
+                    comparisons.push((subject.clone(), Op::GreaterEqual, *min));
+                    comparisons.push((subject, Op::LessEqual, *max));
+                }
+                i = end + 4;
+                continue
+            }
+        }
+        i += 1;
+    }
+    for (subject, op, number) in comparisons {
+        let update = update_of(&mut updates, &subject.variable);
+        let bounds = if subject.length {
+            &mut update.length
+        } else {
+            &mut update.value
+        };
+        bounds.constrain(op, number);
+        compared.push(subject.variable);
+    }
+
+    // The rest is about the characters of words and strings, which needs to
+    // know what the line is about
+    let mut described: Vec<String> = marked.to_vec();
+    if described.is_empty() {
+        described = compared;
+    }
+    if described.is_empty() {
+        // Variables are usually written as single letters
+        described = tokens
+            .iter()
+            .filter_map(|token| match token {
+                Token::Word(word)
+                    if word.chars().count() == 1 && !["a", "A", "I"].contains(&word.as_str()) =>
+                {
+                    Some(word.clone())
+                }
+                _ => None,
+            })
+            .collect();
+    }
+    let charset = charset(tokens);
+    let length = length_in_characters(tokens);
+    if !charset.is_empty() || !length.is_empty() {
+        for variable in described {
+            let update = update_of(&mut updates, &variable);
+            update.length.min = length.min.or(update.length.min);
+            update.length.max = length.max.or(update.length.max);
+            for class in &charset {
+                if !update.charset.contains(class) {
+                    update.charset.push(*class);
+                }
+            }
+        }
+    }
+    updates
+}
+
+fn update_of<'a>(updates: &'a mut Vec<(String, Update)>, variable: &str) -> &'a mut Update {
+    let index = match updates.iter().position(|(name, _)| name == variable) {
+        Some(index) => index,
+        None => {
+            updates.push((variable.to_string(), Update::default()));
+            updates.len() - 1
+        }
+    };
+    &mut updates[index].1
+}
+
+// operand (op operand)+ with at least one number and one subject
+fn comparison_chain(tokens: &[Token], start: usize) -> Option<(Vec<Operand>, Vec<Op>, usize)> {
+    let (first, mut end) = operand(tokens, start)?;
+    let mut operands = vec![first];
+    let mut ops = Vec::new();
+    while let Some(Token::Op(op)) = tokens.get(end) {
+        let Some((next, next_end)) = operand(tokens, end + 1) else {
+            break
+        };
+        ops.push(*op);
+        operands.push(next);
+        end = next_end;
+    }
+    let has_number = operands.iter().any(|operand| matches!(operand, Operand::Number(_)));
+    let has_subject = operands.iter().any(|operand| matches!(operand, Operand::Subjects(_)));
+    (!ops.is_empty() && has_number && has_subject).then_some((operands, ops, end))
+}
+
+fn operand(tokens: &[Token], start: usize) -> Option<(Operand, usize)> {
+    match tokens.get(start)? {
+        Token::Number(number) if !number.is_nan() => Some((Operand::Number(*number), start + 1)),
+        _ => subject_list(tokens, start).map(|(subjects, end)| (Operand::Subjects(subjects), end)),
+    }
+}
+
+// Subjects separated by commas or "and": x, y and z
+fn subject_list(tokens: &[Token], start: usize) -> Option<(Vec<Subject>, usize)> {
+    let (first, mut end) = subject(tokens, start)?;
+    let mut subjects = vec![first];
+    while punct_at(tokens, end, ',') || word_at(tokens, end, &["and"]) {
+        let Some((next, next_end)) = subject(tokens, end + 1) else {
+            break
+        };
+        subjects.push(next);
+        end = next_end;
+    }
+    Some((subjects, end))
+}
+
+// N, A[i], |S|, len(S), length(S), length of S, S.length, S.length() or S
+// length
+fn subject(tokens: &[Token], start: usize) -> Option<(Subject, usize)> {
+    let length = |variable: &str| Subject {
+        variable: variable.to_string(),
+        length: true,
+    };
+    if punct_at(tokens, start, '|') {
+        let variable = variable_at(tokens, start + 1)?;
+        return punct_at(tokens, start + 2, '|').then(|| (length(variable), start + 3))
+    }
+    if word_at(tokens, start, &["len", "length", "size"]) {
+        if punct_at(tokens, start + 1, '(') {
+            let variable = variable_at(tokens, start + 2)?;
+            return punct_at(tokens, start + 3, ')').then(|| (length(variable), start + 4))
+        }
+        if word_at(tokens, start + 1, &["of"]) {
+            let at = start + 2 + usize::from(word_at(tokens, start + 2, &["the"]));
+            return variable_at(tokens, at).map(|variable| (length(variable), at + 1))
+        }
+        return None
+    }
+
+    let variable = variable_at(tokens, start)?;
+    let mut end = start + 1;
+    if punct_at(tokens, end, '.') && word_at(tokens, end + 1, &["length", "len", "size"]) {
+        end += 2;
+        if punct_at(tokens, end, '(') && punct_at(tokens, end + 1, ')') {
+            end += 2;
+        }
+        return Some((length(variable), end))
+    }
+    if word_at(tokens, end, &["length"]) {
+        return Some((length(variable), end + 1))
+    }
+    // The index of an element of a list doesn't matter: A[i]
+    if punct_at(tokens, end, '[') {
+        let close = (end..tokens.len()).find(|&j| punct_at(tokens, j, ']'))?;
+        end = close + 1;
+    }
+    let subject = Subject {
+        variable: variable.to_string(),
+        length: false,
+    };
+    Some((subject, end))
+}
+
+fn variable_at(tokens: &[Token], i: usize) -> Option<&str> {
+    match tokens.get(i)? {
+        Token::Word(word) if !KEYWORDS.contains(&word.to_lowercase().as_str()) => Some(word),
+        _ => None,
+    }
+}
+
+fn word_at(tokens: &[Token], i: usize, words: &[&str]) -> bool {
+    matches!(tokens.get(i), Some(Token::Word(word)) if words.contains(&word.to_lowercase().as_str()))
+}
+
+fn punct_at(tokens: &[Token], i: usize, punct: char) -> bool {
+    tokens.get(i) == Some(&Token::Punct(punct))
+}
+
+// The character classes mentioned on a line
+fn charset(tokens: &[Token]) -> Vec<CharClass> {
+    let mut words = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let Token::Word(word) = token else { continue };
+        // "20 letters" is about the length
+        if i > 0 && matches!(tokens[i - 1], Token::Number(_)) && word_at(tokens, i, &CHARACTERS) {
+            continue
+        }
+        words.push(word.to_lowercase());
+    }
+    // Classes that are mentioned, but not as in "no spaces" or "does not contain
+    // digits"
+    let has = |phrases: &[&str]| {
+        (0..words.len()).any(|i| {
+            let negated = words[i.saturating_sub(2)..i]
+                .iter()
+                .any(|word| ["no", "not", "without", "never"].contains(&word.as_str()));
+            let text = words[i..].join(" ");
+            !negated && phrases.iter().any(|phrase| text.starts_with(phrase))
+        })
+    };
+
+    let mut charset = Vec::new();
+    if has(&["printable", "ascii"]) {
+        charset.extend([
+            CharClass::Lowercase,
+            CharClass::Uppercase,
+            CharClass::Digits,
+            CharClass::Spaces,
+            CharClass::Punctuation,
+        ]);
+        return charset
+    }
+    let lower = has(&["lowercase", "lower case"]);
+    let upper = has(&["uppercase", "upper case", "capital"]);
+    if lower || has(&["alphanumeric"]) || !upper && has(&["letter", "alphabetic"]) {
+        charset.push(CharClass::Lowercase);
+    }
+    if upper || has(&["alphanumeric"]) || !lower && has(&["letter", "alphabetic"]) {
+        charset.push(CharClass::Uppercase);
+    }
+    if has(&["digit", "alphanumeric", "numeric"]) {
+        charset.push(CharClass::Digits);
+    }
+    if has(&["space", "whitespace"]) {
+        charset.push(CharClass::Spaces);
+    }
+    if has(&["punctuation", "symbol", "special"]) {
+        charset.push(CharClass::Punctuation);
+    }
+    charset
+}
+
+// Lengths written as words: at most 20 characters, between 1 and 5 letters
+fn length_in_characters(tokens: &[Token]) -> Bounds {
+    let mut bounds = Bounds::default();
+    for i in 0..tokens.len() {
+        let Some(Token::Number(number)) = tokens.get(i) else {
+            continue
+        };
+        // There may be a few words in between: 5 uppercase letters
+        let unit = (i + 1..tokens.len().min(i + 4))
+            .take_while(|&j| matches!(tokens[j], Token::Word(_)))
+            .any(|j| word_at(tokens, j, &CHARACTERS));
+        if unit {
+            let op = match (i.checked_sub(3), i.checked_sub(2), i.checked_sub(1)) {
+                (Some(j), _, _) if word_at(tokens, j, &["no"]) && word_at(tokens, j + 1, &["more"]) => {
+                    Op::LessEqual
+                }
+                (_, Some(j), _) if word_at(tokens, j, &["at"]) && word_at(tokens, j + 1, &["most"]) => {
+                    Op::LessEqual
+                }
+                (_, Some(j), _) if word_at(tokens, j, &["at"]) && word_at(tokens, j + 1, &["least"]) => {
+                    Op::GreaterEqual
+                }
+                (_, Some(j), _) if word_at(tokens, j, &["up"]) && word_at(tokens, j + 1, &["to"]) => {
+                    Op::LessEqual
+                }
+                (_, Some(j), _)
+                    if word_at(tokens, j, &["less", "fewer"]) && word_at(tokens, j + 1, &["than"]) =>
+                {
+                    Op::Less
+                }
+                (_, Some(j), _) if word_at(tokens, j, &["more"]) && word_at(tokens, j + 1, &["than"]) => {
+                    Op::Greater
+                }
+                (_, _, Some(j)) if word_at(tokens, j, &["exactly"]) => Op::Equal,
+                // between 1 and 20 characters
+                (Some(j), _, _) if word_at(tokens, j, &["between"]) && word_at(tokens, j + 2, &["and"]) => {
+                    if let Token::Number(min) = tokens[j + 1] {
+                        bounds.constrain(Op::GreaterEqual, min);
+                    }
+                    Op::LessEqual
+                }
+                _ => continue,
+            };
+            bounds.constrain(op, *number);
+        }
+    }
+    bounds
+}
+
+impl Op {
+    // The same comparison with the operands swapped
+    fn flipped(self) -> Op {
+        match self {
+            Op::Less => Op::Greater,
+            Op::LessEqual => Op::GreaterEqual,
+            Op::Greater => Op::Less,
+            Op::GreaterEqual => Op::LessEqual,
+            Op::Equal => Op::Equal,
+        }
+    }
+}
+
+impl Bounds {
+    // Apply `subject OP number`
+    fn constrain(&mut self, op: Op, number: f64) {
+        let bound = |inclusive| {
+            Some(Bound {
+                value: number,
+                inclusive,
+            })
+        };
+        match op {
+            Op::Less => self.max = bound(false),
+            Op::LessEqual => self.max = bound(true),
+            Op::Greater => self.min = bound(false),
+            Op::GreaterEqual => self.min = bound(true),
+            Op::Equal => {
+                self.min = bound(true);
+                self.max = bound(true);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds_of(constraints: &[VariableConstraints]) -> Vec<(String, String, String)> {
+        constraints
+            .iter()
+            .map(|c| (c.variable.clone(), c.value.to_string(), c.length.to_string()))
+            .collect()
+    }
+
+    fn strings(values: &[(&str, &str, &str)]) -> Vec<(String, String, String)> {
+        values
+            .iter()
+            .map(|(a, b, c)| (a.to_string(), b.to_string(), c.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_numeric_ranges() {
+        let constraints = parse_constraints(
+            "1 ≤ [[N]] ≤ 100\n0 < {{L}} < 1000\n-10^9 <= `A[i]` <= 10⁹\n1 ≤ x, y ≤ 2*10^5\nK ≥ 3; 1e5 > M",
+        );
+        assert_eq!(
+            bounds_of(&constraints),
+            strings(&[
+                ("N", "[1, 100]", "(-∞, ∞)"),
+                ("L", "(0, 1000)", "(-∞, ∞)"),
+                ("A", "[-1000000000, 1000000000]", "(-∞, ∞)"),
+                ("x", "[1, 200000]", "(-∞, ∞)"),
+                ("y", "[1, 200000]", "(-∞, ∞)"),
+                ("K", "[3, ∞)", "(-∞, ∞)"),
+                ("M", "(-∞, 100000)", "(-∞, ∞)"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_string_lengths() {
+        let constraints = parse_constraints(
+            "1 ≤ length of [[S]] ≤ 50\n0 < |T| < 10\n2 <= len(U) <= 5\n[[W]].length() ≤ 8\n\
+             [[name]] has at most 20 characters\n[[word]] is between 3 and 7 letters long",
+        );
+        assert_eq!(
+            bounds_of(&constraints),
+            strings(&[
+                ("S", "(-∞, ∞)", "[1, 50]"),
+                ("T", "(-∞, ∞)", "(0, 10)"),
+                ("U", "(-∞, ∞)", "[2, 5]"),
+                ("W", "(-∞, ∞)", "(-∞, 8]"),
+                ("name", "(-∞, ∞)", "(-∞, 20]"),
+                ("word", "(-∞, ∞)", "[3, 7]"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_character_sets() {
+        let constraints = parse_constraints(
+            "[[S]] contains only lowercase letters and spaces\n\
+             [[T]] consists of uppercase letters and digits, no spaces\n\
+             [[U]] contains printable ASCII characters\n\
+             1 ≤ length of W ≤ 10, W is made of letters",
+        );
+        let charsets: Vec<(&str, &[CharClass])> =
+            constraints.iter().map(|c| (c.variable.as_str(), c.charset.as_slice())).collect();
+        use CharClass::*;
+        assert_eq!(
+            charsets,
+            [
+                ("S", &[Lowercase, Spaces][..]),
+                ("T", &[Uppercase, Digits][..]),
+                ("U", &[Lowercase, Uppercase, Digits, Spaces, Punctuation][..]),
+                ("W", &[Lowercase, Uppercase][..]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skips_what_it_does_not_understand() {
+        assert!(parse_constraints("The answer always exists.\nN is even").is_empty());
+        assert!(parse_constraints("").is_empty());
+        let constraints = parse_constraints("1 ≤ [[N]] ≤ 10 (N is even)");
+        assert_eq!(bounds_of(&constraints), strings(&[("N", "[1, 10]", "(-∞, ∞)")]));
+    }
+}
@@ -1,5 +1,26 @@
+mod build;
+mod clash_index;
+mod clash_settings;
+mod file_watcher;
 mod formatter;
+mod golf_records;
+mod history;
+mod json_file;
 mod lines_with_endings;
+mod local_testcases;
 mod outputstyle;
+mod report;
+mod run_profile;
+mod solution_archive;
 
+pub use build::{hash_files, run_build, BuildCache};
+pub use clash_index::{ClashIndex, IndexEntry};
+pub use clash_settings::ClashSettings;
+pub use file_watcher::FileWatcher;
+pub use golf_records::GolfRecords;
+pub use history::{format_timestamp, Attempt, ClashStatus, History, Outcome};
+pub use local_testcases::{LocalTestcase, LocalTestcases};
 pub use outputstyle::OutputStyle;
+pub use report::{codingame_score, Report, ReportFormat};
+pub use run_profile::{RunProfiles, SolutionCommands};
+pub use solution_archive::{SolutionArchive, SolutionRef};
@@ -0,0 +1,245 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use wait_timeout::ChildExt;
+
+use super::json_file::load_json_or_default;
+
+/// Hashes of the source files of the last successful build of each build
+/// command.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BuildCache(BTreeMap<String, String>);
+
+impl BuildCache {
+    /// Read the cache from `path`.
+    pub fn load(path: &Path) -> Result<Self> {
+        load_json_or_default(path, "build cache")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, contents).with_context(|| format!("Unable to write {:?}", path))
+    }
+
+    /// Whether `build_command` was last built successfully from sources with
+    /// the hash `sources_hash`.
+    pub fn is_fresh(&self, build_command: &Command, sources_hash: &str) -> bool {
+        self.0.get(&cache_key(build_command)).is_some_and(|hash| hash == sources_hash)
+    }
+
+    pub fn record(&mut self, build_command: &Command, sources_hash: String) {
+        self.0.insert(cache_key(build_command), sources_hash);
+    }
+
+    pub fn invalidate(&mut self, build_command: &Command) {
+        self.0.remove(&cache_key(build_command));
+    }
+}
+
+// The same command can build different things in different directories.
+fn cache_key(build_command: &Command) -> String {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    format!("{} {:?}", cwd.display(), build_command)
+}
+
+/// Hash the paths and contents of `files` into a hex string. The order of
+/// the files does not matter.
+pub fn hash_files(files: &[PathBuf]) -> Result<String> {
+    let mut files: Vec<&PathBuf> = files.iter().collect();
+    files.sort();
+    files.dedup();
+    let mut hasher = Sha256::new();
+    for file in files {
+        let contents =
+            std::fs::read(file).with_context(|| format!("Unable to read source file {:?}", file))?;
+        hasher.update(file.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update((contents.len() as u64).to_le_bytes());
+        hasher.update(&contents);
+    }
+    Ok(hasher.finalize().iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+/// Run `build_command`, streaming its output to STDERR as it is printed.
+/// While the build runs the elapsed time is shown below the output (if
+/// STDERR is a terminal). The build is killed after `timeout`.
+///
+/// Returns the time the build took.
+pub fn run_build(build_command: &mut Command, timeout: Duration) -> Result<Duration> {
+    build_command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+    #[cfg(unix)]
+    {
+        // Compilers spawn processes of their own, kill them too on timeout
+        use std::os::unix::process::CommandExt;
+        build_command.process_group(0);
+    }
+
+    let started = Instant::now();
+    let mut build = build_command
+        .spawn()
+        .with_context(|| format!("Unable to run build command {:?}", build_command.get_program()))?;
+    let progress = Progress::new(started);
+    let stdout = build.stdout.take().expect("STDOUT should be piped");
+    let stderr = build.stderr.take().expect("STDERR should be piped");
+
+    let status = std::thread::scope(|scope| {
+        scope.spawn(|| progress.stream(stdout));
+        scope.spawn(|| progress.stream(stderr));
+        loop {
+            progress.redraw();
+            let elapsed = started.elapsed();
+            if elapsed >= timeout {
+                kill_build(&mut build);
+                break None
+            }
+            let tick = Duration::from_millis(100).min(timeout - elapsed);
+            match build.wait_timeout(tick) {
+                Ok(Some(status)) => break Some(Ok(status)),
+                Ok(None) => continue,
+                Err(err) => {
+                    kill_build(&mut build);
+                    break Some(Err(err))
+                }
+            }
+        }
+    });
+    progress.clear();
+    let elapsed = started.elapsed();
+
+    match status {
+        None => Err(anyhow!("Build timed out after {:.2}s", timeout.as_secs_f64())),
+        Some(Err(err)) => Err(err).context("Unable to wait for build command"),
+        Some(Ok(status)) if status.success() => Ok(elapsed),
+        Some(Ok(status)) => Err(anyhow!("Build failed after {:.2}s ({status})", elapsed.as_secs_f64())),
+    }
+}
+
+fn kill_build(build: &mut std::process::Child) {
+    #[cfg(unix)]
+    // SAFETY: killpg has no memory safety requirements. The build is not
+    // reaped before this so its process group still exists.
+    unsafe {
+        libc::killpg(build.id() as libc::pid_t, libc::SIGKILL);
+    }
+    let _ = build.kill();
+    let _ = build.wait();
+}
+
+/// Interleaves build output with a line showing the elapsed build time.
+struct Progress {
+    started: Instant,
+    // Whether the elapsed time line is drawn at all
+    interactive: bool,
+    lock: Mutex<()>,
+}
+
+impl Progress {
+    fn new(started: Instant) -> Self {
+        Progress {
+            started,
+            interactive: std::io::stderr().is_terminal(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn stream(&self, output: impl Read) {
+        let mut reader = BufReader::new(output);
+        let mut line = Vec::new();
+        while matches!(reader.read_until(b'\n', &mut line), Ok(n) if n > 0) {
+            if !line.ends_with(b"\n") {
+                line.push(b'\n');
+            }
+            let _guard = self.lock.lock().unwrap_or_else(|err| err.into_inner());
+            let mut stderr = std::io::stderr().lock();
+            if self.interactive {
+                let _ = stderr.write_all(b"\r\x1b[K");
+            }
+            let _ = stderr.write_all(&line);
+            self.draw(&mut stderr);
+            line.clear();
+        }
+    }
+
+    fn redraw(&self) {
+        let _guard = self.lock.lock().unwrap_or_else(|err| err.into_inner());
+        self.draw(&mut std::io::stderr().lock());
+    }
+
+    fn draw(&self, stderr: &mut impl Write) {
+        if self.interactive {
+            let _ = write!(stderr, "\r\x1b[KBuilding... {:.1}s", self.started.elapsed().as_secs_f64());
+            let _ = stderr.flush();
+        }
+    }
+
+    fn clear(&self) {
+        if self.interactive {
+            let _ = write!(std::io::stderr().lock(), "\r\x1b[K");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_depends_on_contents_not_order() {
+        let dir = std::env::temp_dir().join(format!("coctus-build-hash-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let a = dir.join("a.rs");
+        let b = dir.join("b.rs");
+        std::fs::write(&a, "fn main() {}").unwrap();
+        std::fs::write(&b, "mod a;").unwrap();
+
+        let hash = hash_files(&[a.clone(), b.clone()]).unwrap();
+        assert_eq!(hash, hash_files(&[b.clone(), a.clone()]).unwrap());
+        std::fs::write(&b, "mod a; ").unwrap();
+        assert_ne!(hash, hash_files(&[a.clone(), b.clone()]).unwrap());
+        assert!(hash_files(&[dir.join("missing.rs")]).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cache_is_keyed_by_build_command() {
+        let mut release = Command::new("cargo");
+        release.args(["build", "--release"]);
+        let mut debug = Command::new("cargo");
+        debug.arg("build");
+
+        let mut cache = BuildCache::default();
+        cache.record(&release, "abc".to_string());
+        assert!(cache.is_fresh(&release, "abc"));
+        assert!(!cache.is_fresh(&release, "def"));
+        assert!(!cache.is_fresh(&debug, "abc"));
+        cache.invalidate(&release);
+        assert!(!cache.is_fresh(&release, "abc"));
+    }
+
+    #[test]
+    fn test_build_timeout() {
+        let mut build = Command::new("sh");
+        build.args(["-c", "sleep 30 & wait"]);
+        let started = Instant::now();
+        let err = run_build(&mut build, Duration::from_millis(200)).unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{err}");
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_failed_build() {
+        let mut build = Command::new("sh");
+        build.args(["-c", "echo compiling; exit 3"]);
+        let err = run_build(&mut build, Duration::MAX).unwrap_err();
+        assert!(err.to_string().contains("Build failed"), "{err}");
+        assert!(run_build(&mut Command::new("true"), Duration::MAX).is_ok());
+    }
+}
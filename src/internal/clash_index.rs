@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use anyhow::{Context, Result};
+use clashlib::clash::{Clash, PublicHandle, PuzzleType};
+use serde::{Deserialize, Serialize};
+
+use super::json_file::load_json_or_default;
+
+/// Metadata of the locally stored clashes, so they can be counted and
+/// filtered without reading every clash file. Keyed by clash handle.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ClashIndex {
+    #[serde(default)]
+    clashes: BTreeMap<String, IndexEntry>,
+    // Size and modification time of the clash files that couldn't be read,
+    // so they are only warned about again once they change
+    #[serde(default)]
+    broken: BTreeMap<String, (u64, u64)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub handle: String,
+    pub title: String,
+    pub puzzle_type: PuzzleType,
+    pub fastest: bool,
+    pub shortest: bool,
+    pub reverse: bool,
+    pub upvotes: i32,
+    pub downvotes: i32,
+    pub num_testcases: usize,
+    pub has_stub_generator: bool,
+    /// When the clash was fetched, in seconds since the Unix epoch.
+    pub fetched_at: u64,
+    // Size and modification time of the clash file when it was indexed, to
+    // notice when it changes
+    file_size: u64,
+    file_modified: u64,
+}
+
+impl IndexEntry {
+    /// Whether the clash has all the modes that are `true`.
+    pub fn has_modes(&self, fastest: bool, shortest: bool, reverse: bool) -> bool {
+        (!fastest || self.fastest) && (!shortest || self.shortest) && (!reverse || self.reverse)
+    }
+}
+
+impl ClashIndex {
+    /// Read the index from `path`.
+    pub fn load(path: &Path) -> Result<Self> {
+        load_json_or_default(path, "clash index")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = serde_json::to_string(self)?;
+        std::fs::write(path, contents).with_context(|| format!("Unable to write {:?}", path))
+    }
+
+    /// Bring the index up to date with the clash files in `clash_dir`: new
+    /// and changed files are indexed, entries of removed files are dropped.
+    /// Files that can't be read as clashes are skipped with a warning, which
+    /// is only given again once they change.
+    /// Returns whether anything changed.
+    pub fn sync(&mut self, clash_dir: &Path) -> Result<bool> {
+        let entries = match std::fs::read_dir(clash_dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                let changed = !self.clashes.is_empty() || !self.broken.is_empty();
+                self.clashes.clear();
+                self.broken.clear();
+                return Ok(changed)
+            }
+            Err(err) => return Err(err).with_context(|| format!("Unable to read {:?}", clash_dir)),
+        };
+
+        let mut changed = false;
+        let mut indexed = BTreeMap::new();
+        let mut broken = BTreeMap::new();
+        for entry in entries {
+            let entry = entry.with_context(|| format!("Unable to read {:?}", clash_dir))?;
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let Some(handle) = file_name.strip_suffix(".json") else {
+                continue
+            };
+            let Ok((file_size, file_modified)) = file_stamp(&entry.path()) else {
+                continue
+            };
+            let was_broken = self.broken.remove(handle);
+            match self.clashes.remove(handle) {
+                Some(entry) if entry.file_size == file_size && entry.file_modified == file_modified => {
+                    indexed.insert(handle.to_string(), entry);
+                }
+                _ if was_broken == Some((file_size, file_modified)) => {
+                    broken.insert(handle.to_string(), (file_size, file_modified));
+                }
+                _ => match read_clash(&entry.path()) {
+                    Ok(clash) => {
+                        changed = true;
+                        let fetched_at = file_modified / 1_000_000_000;
+                        let entry = IndexEntry::new(handle, &clash, fetched_at, (file_size, file_modified));
+                        indexed.insert(handle.to_string(), entry);
+                    }
+                    Err(err) => {
+                        changed = true;
+                        broken.insert(handle.to_string(), (file_size, file_modified));
+                        eprintln!("Skipping {:?} in the clash index: {err:#}", entry.path());
+                    }
+                },
+            }
+        }
+        changed |= !self.clashes.is_empty() || !self.broken.is_empty();
+        self.clashes = indexed;
+        self.broken = broken;
+        Ok(changed)
+    }
+
+    /// Index a clash that was just saved to `clash_file`.
+    pub fn update(
+        &mut self,
+        handle: &PublicHandle,
+        clash: &Clash,
+        clash_file: &Path,
+        fetched_at: u64,
+    ) -> Result<()> {
+        let stamp = file_stamp(clash_file).with_context(|| format!("Unable to read {:?}", clash_file))?;
+        let handle = handle.to_string();
+        let entry = IndexEntry::new(&handle, clash, fetched_at, stamp);
+        self.broken.remove(&handle);
+        self.clashes.insert(handle, entry);
+        Ok(())
+    }
+
+    /// All entries, sorted by handle.
+    pub fn entries(&self) -> impl Iterator<Item = &IndexEntry> {
+        self.clashes.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.clashes.len()
+    }
+}
+
+impl IndexEntry {
+    fn new(handle: &str, clash: &Clash, fetched_at: u64, (file_size, file_modified): (u64, u64)) -> Self {
+        IndexEntry {
+            handle: handle.to_string(),
+            title: clash.title().to_string(),
+            puzzle_type: clash.puzzle_type(),
+            fastest: clash.is_fastest(),
+            shortest: clash.is_shortest(),
+            reverse: clash.is_reverse(),
+            upvotes: clash.upvotes(),
+            downvotes: clash.downvotes(),
+            num_testcases: clash.testcases().len(),
+            has_stub_generator: clash.stub_generator().is_some_and(|stub| !stub.trim().is_empty()),
+            fetched_at,
+            file_size,
+            file_modified,
+        }
+    }
+}
+
+fn read_clash(path: &Path) -> Result<Clash> {
+    let contents = std::fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+// Size and modification time (in nanoseconds since the Unix epoch)
+fn file_stamp(path: &Path) -> std::io::Result<(u64, u64)> {
+    let metadata = std::fs::metadata(path)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_nanos() as u64);
+    Ok((metadata.len(), modified))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_is_incremental() {
+        let dir = std::env::temp_dir().join(format!("coctus-index-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let fixture = std::fs::read_to_string("fixtures/puzzles/stub_and_solution_tester.json").unwrap();
+        std::fs::write(dir.join("aaa.json"), &fixture).unwrap();
+        std::fs::write(dir.join("broken.json"), "{").unwrap();
+
+        let mut index = ClashIndex::default();
+        assert!(index.sync(&dir).unwrap());
+        assert_eq!(index.len(), 1);
+        let entry = index.entries().next().unwrap().clone();
+        assert_eq!(entry.handle, "aaa");
+        assert!(entry.has_stub_generator);
+        assert!(entry.num_testcases > 0);
+        // The broken file is remembered and not read again until it changes
+        assert!(!index.sync(&dir).unwrap());
+        std::fs::write(dir.join("broken.json"), &fixture).unwrap();
+        assert!(index.sync(&dir).unwrap());
+        assert_eq!(index.len(), 2);
+        std::fs::remove_file(dir.join("broken.json")).unwrap();
+        assert!(index.sync(&dir).unwrap());
+        assert_eq!(index.len(), 1);
+        std::fs::write(dir.join("broken.json"), "{").unwrap();
+        assert!(index.sync(&dir).unwrap());
+        std::fs::remove_file(dir.join("broken.json")).unwrap();
+        assert!(index.sync(&dir).unwrap());
+        assert!(!index.sync(&dir).unwrap());
+
+        std::fs::write(dir.join("bbb.json"), &fixture).unwrap();
+        assert!(index.sync(&dir).unwrap());
+        assert_eq!(index.len(), 2);
+        std::fs::remove_file(dir.join("aaa.json")).unwrap();
+        assert!(index.sync(&dir).unwrap());
+        assert_eq!(index.len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(index.sync(&dir).unwrap());
+        assert_eq!(index.len(), 0);
+    }
+}
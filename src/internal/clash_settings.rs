@@ -0,0 +1,69 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use clashlib::clash::PublicHandle;
+use clashlib::solution::Comparator;
+use serde::{Deserialize, Serialize};
+
+use super::json_file::load_json_or_default;
+
+/// Settings the user has chosen for individual clashes, keyed by clash handle.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ClashSettings(BTreeMap<String, Settings>);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Settings {
+    /// How the output of solutions is compared to the expected output, in
+    /// the same format as the --compare option.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    compare: Option<String>,
+}
+
+impl ClashSettings {
+    /// Read the settings from `path`.
+    pub fn load(path: &Path) -> Result<Self> {
+        load_json_or_default(path, "clash settings")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, contents).with_context(|| format!("Unable to write {:?}", path))
+    }
+
+    pub fn comparator(&self, handle: &PublicHandle) -> Result<Option<Comparator>> {
+        let Some(compare) = self.0.get(&handle.to_string()).and_then(|settings| settings.compare.as_ref())
+        else {
+            return Ok(None)
+        };
+        let comparator = Comparator::from_str(compare)
+            .with_context(|| format!("Invalid comparator in the settings of clash {handle}"))?;
+        Ok(Some(comparator))
+    }
+
+    /// Set the comparator used for `handle`. The default comparator (exact)
+    /// is not stored.
+    pub fn set_comparator(&mut self, handle: &PublicHandle, comparator: Comparator) {
+        let compare = Some(comparator.to_string()).filter(|_| comparator != Comparator::default());
+        self.0.entry(handle.to_string()).or_default().compare = compare;
+        self.0.retain(|_, settings| settings.compare.is_some());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_comparator() {
+        let handle = PublicHandle::from_str("abc123").unwrap();
+        let mut settings = ClashSettings::default();
+        assert_eq!(settings.comparator(&handle).unwrap(), None);
+        settings.set_comparator(&handle, Comparator::UnorderedLines);
+        assert_eq!(settings.comparator(&handle).unwrap(), Some(Comparator::UnorderedLines));
+        settings.set_comparator(&handle, Comparator::Exact);
+        assert_eq!(settings.comparator(&handle).unwrap(), None);
+        assert!(settings.0.is_empty());
+    }
+}
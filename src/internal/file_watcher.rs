@@ -0,0 +1,127 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Detects changes to files by polling their modification times and sizes.
+/// Directories are watched recursively, skipping hidden entries.
+pub struct FileWatcher {
+    paths: Vec<PathBuf>,
+    snapshot: Vec<(PathBuf, Option<SystemTime>, u64)>,
+}
+
+impl FileWatcher {
+    const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        let snapshot = snapshot(&paths);
+        FileWatcher { paths, snapshot }
+    }
+
+    /// Returns true if any of the watched files has been created, modified
+    /// or removed since the last call.
+    pub fn changed(&mut self) -> bool {
+        let snapshot = snapshot(&self.paths);
+        let changed = snapshot != self.snapshot;
+        self.snapshot = snapshot;
+        changed
+    }
+
+    /// Call `run` and then block until a watched file changes. Files that
+    /// `run` itself writes, like build outputs, don't count as changes.
+    pub fn run_then_wait(&mut self, run: impl FnOnce()) {
+        run();
+        self.snapshot = snapshot(&self.paths);
+        self.wait_for_change();
+    }
+
+    /// Block until a watched file changes.
+    pub fn wait_for_change(&mut self) {
+        while !self.changed() {
+            std::thread::sleep(Self::POLL_INTERVAL);
+        }
+        // Editors often save in several steps, give them time to finish.
+        std::thread::sleep(Self::POLL_INTERVAL);
+        self.changed();
+    }
+}
+
+fn snapshot(paths: &[PathBuf]) -> Vec<(PathBuf, Option<SystemTime>, u64)> {
+    let mut entries = Vec::new();
+    for path in paths {
+        add_entries(path, &mut entries);
+    }
+    entries
+}
+
+fn add_entries(path: &Path, entries: &mut Vec<(PathBuf, Option<SystemTime>, u64)>) {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        // Missing files are included so that creating them counts as a change
+        entries.push((path.to_path_buf(), None, 0));
+        return
+    };
+    if !metadata.is_dir() {
+        entries.push((path.to_path_buf(), metadata.modified().ok(), metadata.len()));
+        return
+    }
+    let Ok(dir) = std::fs::read_dir(path) else { return };
+    let mut children: Vec<PathBuf> = dir
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .map(|entry| entry.path())
+        .collect();
+    children.sort();
+    for child in children {
+        add_entries(&child, entries);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_changes() {
+        let dir = std::env::temp_dir().join(format!("coctus-watch-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("sol.py");
+        std::fs::write(&file, "print(1)").unwrap();
+
+        let mut watcher = FileWatcher::new(vec![dir.clone()]);
+        assert!(!watcher.changed());
+        std::fs::write(&file, "print(12)").unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+        std::fs::write(dir.join(".hidden"), "").unwrap();
+        assert!(!watcher.changed());
+        std::fs::remove_file(&file).unwrap();
+        assert!(watcher.changed());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_output_of_run_is_not_a_change() {
+        let dir = std::env::temp_dir().join(format!("coctus-watch-output-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("sol.c");
+        std::fs::write(&source, "int main() {}").unwrap();
+
+        let mut watcher = FileWatcher::new(vec![dir.clone()]);
+        let edit = {
+            let source = source.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(1000));
+                std::fs::write(source, "int main() { return 0; }").unwrap();
+            })
+        };
+        let start = std::time::Instant::now();
+        // Like a build that writes its binary next to the source
+        watcher.run_then_wait(|| std::fs::write(dir.join("sol"), "binary").unwrap());
+        assert!(
+            start.elapsed() >= Duration::from_millis(1000),
+            "the build output should not be a change"
+        );
+        edit.join().unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
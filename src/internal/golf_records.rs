@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use clashlib::clash::PublicHandle;
+use serde::{Deserialize, Serialize};
+
+use super::json_file::load_json_or_default;
+
+/// Lengths of the shortest passing solutions, keyed by clash handle and
+/// language.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GolfRecords(BTreeMap<String, BTreeMap<String, usize>>);
+
+impl GolfRecords {
+    /// Read the records from `path`.
+    pub fn load(path: &Path) -> Result<Self> {
+        load_json_or_default(path, "golf records")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, contents).with_context(|| format!("Unable to write {:?}", path))
+    }
+
+    pub fn best(&self, handle: &PublicHandle, language: &str) -> Option<usize> {
+        self.0.get(&handle.to_string())?.get(language).copied()
+    }
+
+    /// Record the length of a passing solution. Returns the previous best
+    /// length, the record is only updated if `length` is shorter.
+    pub fn record(&mut self, handle: &PublicHandle, language: &str, length: usize) -> Option<usize> {
+        let previous_best = self.best(handle, language);
+        if previous_best.map_or(true, |best| length < best) {
+            self.0.entry(handle.to_string()).or_default().insert(language.to_string(), length);
+        }
+        previous_best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_record_keeps_shortest() {
+        let handle = PublicHandle::from_str("abc123").unwrap();
+        let mut records = GolfRecords::default();
+        assert_eq!(records.record(&handle, "py", 50), None);
+        assert_eq!(records.record(&handle, "py", 60), Some(50));
+        assert_eq!(records.best(&handle, "py"), Some(50));
+        assert_eq!(records.record(&handle, "py", 40), Some(50));
+        assert_eq!(records.best(&handle, "py"), Some(40));
+    }
+
+    #[test]
+    fn test_records_are_per_language() {
+        let handle = PublicHandle::from_str("abc123").unwrap();
+        let mut records = GolfRecords::default();
+        records.record(&handle, "py", 50);
+        assert_eq!(records.best(&handle, "rb"), None);
+    }
+}
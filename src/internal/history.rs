@@ -0,0 +1,258 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use clashlib::clash::PublicHandle;
+use serde::{Deserialize, Serialize};
+
+/// One `coctus run` of a solution against a clash, as stored in the history
+/// file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attempt {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub handle: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    pub command: String,
+    /// SHA-256 of the solution source files (if they are known).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub solution_hash: Option<String>,
+    pub outcome: Outcome,
+    #[serde(default)]
+    pub testcases: Vec<TestcaseOutcome>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Passed,
+    Failed,
+    BuildFailed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestcaseOutcome {
+    pub index: usize,
+    /// Same as the status in `run --format json` reports.
+    pub status: String,
+}
+
+/// Where the attempts at a clash have got to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClashStatus {
+    Solved,
+    /// Not solved yet, but it is still the current clash.
+    InProgress,
+    /// Moved on from without solving it.
+    Abandoned,
+}
+
+/// Summary of all the attempts at one clash.
+#[derive(Debug)]
+pub struct ClashHistory<'a> {
+    pub handle: &'a str,
+    pub attempts: Vec<&'a Attempt>,
+    pub status: ClashStatus,
+}
+
+impl Attempt {
+    pub fn num_passed(&self) -> usize {
+        self.testcases.iter().filter(|testcase| testcase.status == "pass").count()
+    }
+
+    /// The testcase results as one character each: `.` for passing, `-` for
+    /// skipped, `F` for wrong output, `T` for timeouts and `E` for other
+    /// errors.
+    pub fn result_symbols(&self) -> String {
+        self.testcases
+            .iter()
+            .map(|testcase| match testcase.status.as_str() {
+                "pass" => '.',
+                "skipped" => '-',
+                "wrong_output" => 'F',
+                "timeout" => 'T',
+                _ => 'E',
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for ClashStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClashStatus::Solved => write!(f, "solved"),
+            ClashStatus::InProgress => write!(f, "in progress"),
+            ClashStatus::Abandoned => write!(f, "abandoned"),
+        }
+    }
+}
+
+/// Append-only log of [Attempt]s, one JSON object per line.
+pub struct History;
+
+impl History {
+    pub fn append(path: &Path, attempt: &Attempt) -> Result<()> {
+        let mut line = serde_json::to_string(attempt)?;
+        line.push('\n');
+        // A single write so concurrent runs don't interleave their lines
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .with_context(|| format!("Unable to write to {:?}", path))
+    }
+
+    /// Read all attempts in the order they were made. A missing file means
+    /// there is no history yet.
+    pub fn load(path: &Path) -> Result<Vec<Attempt>> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err).with_context(|| format!("Unable to read {:?}", path)),
+        };
+        contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(line_index, line)| {
+                serde_json::from_str(line)
+                    .with_context(|| format!("Unable to parse line {} of {:?}", line_index + 1, path))
+            })
+            .collect()
+    }
+
+    /// Group `attempts` by clash, most recently attempted clash first.
+    pub fn by_clash<'a>(attempts: &'a [Attempt], current: Option<&PublicHandle>) -> Vec<ClashHistory<'a>> {
+        let mut clashes: BTreeMap<&str, Vec<&Attempt>> = BTreeMap::new();
+        for attempt in attempts {
+            clashes.entry(&attempt.handle).or_default().push(attempt);
+        }
+        let current = current.map(|handle| handle.to_string());
+        let mut clashes: Vec<ClashHistory> = clashes
+            .into_iter()
+            .map(|(handle, attempts)| {
+                let status = if attempts.iter().any(|attempt| attempt.outcome == Outcome::Passed) {
+                    ClashStatus::Solved
+                } else if current.as_deref() == Some(handle) {
+                    ClashStatus::InProgress
+                } else {
+                    ClashStatus::Abandoned
+                };
+                ClashHistory {
+                    handle,
+                    attempts,
+                    status,
+                }
+            })
+            .collect();
+        clashes.sort_by_key(|clash| std::cmp::Reverse(clash.last_timestamp()));
+        clashes
+    }
+}
+
+impl ClashHistory<'_> {
+    pub fn last_timestamp(&self) -> u64 {
+        self.attempts.iter().map(|attempt| attempt.timestamp).max().unwrap_or(0)
+    }
+
+    /// The attempt that passed the most testcases (the latest one on ties).
+    pub fn best(&self) -> Option<&Attempt> {
+        self.attempts.iter().copied().max_by_key(|attempt| attempt.num_passed())
+    }
+}
+
+/// Format seconds since the Unix epoch as a UTC date and time.
+pub fn format_timestamp(timestamp: u64) -> String {
+    let (days, seconds) = (timestamp / 86400, timestamp % 86400);
+    // Converts days since 1970-01-01 to a date in the proleptic Gregorian
+    // calendar, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z / 146097;
+    let day_of_era = z % 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attempt(handle: &str, timestamp: u64, outcome: Outcome, statuses: &[&str]) -> Attempt {
+        Attempt {
+            timestamp,
+            handle: handle.to_string(),
+            language: Some("rust".to_string()),
+            command: "./solution".to_string(),
+            solution_hash: None,
+            outcome,
+            testcases: statuses
+                .iter()
+                .enumerate()
+                .map(|(index, status)| TestcaseOutcome {
+                    index: index + 1,
+                    status: status.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_append_and_load() {
+        let path = std::env::temp_dir().join(format!("coctus-history-test-{}.jsonl", std::process::id()));
+        let first = attempt("abc", 10, Outcome::Failed, &["pass", "wrong_output", "skipped"]);
+        let second = attempt("abc", 20, Outcome::BuildFailed, &[]);
+        History::append(&path, &first).unwrap();
+        History::append(&path, &second).unwrap();
+        assert_eq!(History::load(&path).unwrap(), [first, second]);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(History::load(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_clash_status() {
+        let attempts = [
+            attempt("aaa", 10, Outcome::Failed, &["pass", "timeout"]),
+            attempt("aaa", 20, Outcome::Passed, &["pass", "pass"]),
+            attempt("bbb", 30, Outcome::Failed, &["wrong_output", "skipped"]),
+            attempt("bbb", 35, Outcome::Failed, &["timeout", "skipped"]),
+            attempt("ccc", 40, Outcome::BuildFailed, &[]),
+        ];
+        let current = "ccc".parse::<PublicHandle>().unwrap();
+        let clashes = History::by_clash(&attempts, Some(&current));
+        let statuses: Vec<_> = clashes.iter().map(|clash| (clash.handle, clash.status)).collect();
+        assert_eq!(
+            statuses,
+            [
+                ("ccc", ClashStatus::InProgress),
+                ("bbb", ClashStatus::Abandoned),
+                ("aaa", ClashStatus::Solved)
+            ]
+        );
+        assert_eq!(clashes[2].best().unwrap().timestamp, 20);
+        // Ties go to the latest attempt
+        assert_eq!(clashes[1].best().unwrap().timestamp, 35);
+        assert_eq!(attempts[0].result_symbols(), ".T");
+        assert_eq!(attempts[2].result_symbols(), "F-");
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(951782400 + 3661), "2000-02-29 01:01:01 UTC");
+        assert_eq!(format_timestamp(1792224000), "2026-10-17 08:00:00 UTC");
+    }
+}
@@ -0,0 +1,15 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+
+/// Deserialize the JSON file at `path`, described as `what` in errors. A
+/// missing file gives the default value, as nothing has been saved yet.
+pub fn load_json_or_default<T: DeserializeOwned + Default>(path: &Path, what: &str) -> Result<T> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .with_context(|| format!("Unable to deserialize {what} from {:?}", path)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(err) => Err(err).with_context(|| format!("Unable to read {:?}", path)),
+    }
+}
@@ -0,0 +1,225 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use clashlib::clash::{PublicHandle, Testcase};
+use serde::{Deserialize, Serialize};
+
+use super::json_file::load_json_or_default;
+
+// Title prefix that marks local testcases among the testcases of a clash
+const LOCAL_MARKER: &str = "[local]";
+
+/// Testcases added to a clash by the user, stored as `DIR/HANDLE.json`.
+/// They come after the testcases of the clash and are numbered after them.
+#[derive(Debug)]
+pub struct LocalTestcases {
+    path: PathBuf,
+    testcases: Vec<LocalTestcase>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocalTestcase {
+    pub title: String,
+    pub test_in: String,
+    pub test_out: String,
+}
+
+impl LocalTestcases {
+    /// Read the local testcases of a clash.
+    pub fn load(dir: &Path, handle: &PublicHandle) -> Result<Self> {
+        let path = dir.join(format!("{handle}.json"));
+        let testcases = load_json_or_default(&path, "local testcases")?;
+        Ok(LocalTestcases { path, testcases })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if self.testcases.is_empty() {
+            return match std::fs::remove_file(&self.path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    Err(err).with_context(|| format!("Unable to remove {:?}", self.path))
+                }
+                _ => Ok(()),
+            }
+        }
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("Unable to create directory {:?}", dir))?;
+        }
+        let contents = serde_json::to_string_pretty(&self.testcases)?;
+        std::fs::write(&self.path, contents).with_context(|| format!("Unable to write {:?}", self.path))
+    }
+
+    pub fn len(&self) -> usize {
+        self.testcases.len()
+    }
+
+    pub fn push(&mut self, testcase: LocalTestcase) {
+        self.testcases.push(testcase);
+    }
+
+    /// The local testcase that is testcase `index` of the clash when the
+    /// clash itself has `num_clash_testcases` testcases.
+    pub fn get_mut(&mut self, index: usize, num_clash_testcases: usize) -> Result<&mut LocalTestcase> {
+        let position = self.position(index, num_clash_testcases)?;
+        Ok(&mut self.testcases[position])
+    }
+
+    pub fn remove(&mut self, index: usize, num_clash_testcases: usize) -> Result<LocalTestcase> {
+        let position = self.position(index, num_clash_testcases)?;
+        Ok(self.testcases.remove(position))
+    }
+
+    fn position(&self, index: usize, num_clash_testcases: usize) -> Result<usize> {
+        let last = num_clash_testcases + self.testcases.len();
+        match index {
+            _ if index > last || index == 0 => {
+                Err(anyhow!("Invalid testcase index {index} (the last one is {last})"))
+            }
+            _ if index <= num_clash_testcases => Err(anyhow!(
+                "Testcase {index} belongs to the clash, local testcases are {} to {last}",
+                num_clash_testcases + 1
+            )),
+            _ => Ok(index - num_clash_testcases - 1),
+        }
+    }
+
+    /// `clash_testcases` followed by the local testcases, whose titles are
+    /// marked as local.
+    pub fn merged_with(&self, clash_testcases: &[Testcase]) -> Vec<Testcase> {
+        let local = self.testcases.iter().enumerate().map(|(i, local)| Testcase {
+            index: clash_testcases.len() + i + 1,
+            title: format!("{LOCAL_MARKER} {}", local.title),
+            test_in: local.test_in.clone(),
+            test_out: local.test_out.clone(),
+            is_validator: false,
+        });
+        clash_testcases.iter().cloned().chain(local).collect()
+    }
+}
+
+impl LocalTestcase {
+    /// The testcase as text for editing, see [LocalTestcase::from_editable].
+    pub fn to_editable(&self) -> String {
+        format!(
+            "{TITLE_HEADER}\n{}\n{INPUT_HEADER}\n{}\n{OUTPUT_HEADER}\n{}\n",
+            self.title, self.test_in, self.test_out
+        )
+    }
+
+    /// Parse a testcase written as sections that start with header lines:
+    ///
+    /// ```text
+    /// ===== TITLE ======
+    /// Empty list
+    /// ===== INPUT ======
+    /// 0
+    /// ===== OUTPUT =====
+    /// NONE
+    /// ```
+    ///
+    /// Trailing newlines of the sections are dropped, like in the testcases
+    /// of clashes.
+    pub fn from_editable(text: &str) -> Result<Self> {
+        let mut sections: [Option<String>; 3] = Default::default();
+        let mut current: Option<usize> = None;
+        for line in text.lines() {
+            let header = [TITLE_HEADER, INPUT_HEADER, OUTPUT_HEADER]
+                .iter()
+                .position(|h| line.trim_end() == *h);
+            match (header, current) {
+                (Some(header), _) => {
+                    if sections[header].is_some() {
+                        return Err(anyhow!("Duplicate section {}", line.trim()))
+                    }
+                    sections[header] = Some(String::new());
+                    current = Some(header);
+                }
+                (None, Some(section)) => {
+                    let section = sections[section].as_mut().expect("current section should exist");
+                    section.push_str(line);
+                    section.push('\n');
+                }
+                (None, None) if line.trim().is_empty() => {}
+                (None, None) => return Err(anyhow!("Expected {TITLE_HEADER} before {line:?}")),
+            }
+        }
+        let [Some(title), Some(test_in), Some(test_out)] = sections else {
+            return Err(anyhow!("The testcase needs a title, an input and an output section"))
+        };
+        let title = title.trim().to_string();
+        if title.is_empty() {
+            return Err(anyhow!("The testcase needs a title"))
+        }
+        Ok(LocalTestcase {
+            title,
+            test_in: test_in.trim_end_matches('\n').to_string(),
+            test_out: test_out.trim_end_matches('\n').to_string(),
+        })
+    }
+}
+
+const TITLE_HEADER: &str = "===== TITLE ======";
+const INPUT_HEADER: &str = "===== INPUT ======";
+const OUTPUT_HEADER: &str = "===== OUTPUT =====";
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn local(title: &str) -> LocalTestcase {
+        LocalTestcase {
+            title: title.to_string(),
+            test_in: "1 2\n3".to_string(),
+            test_out: "6".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_editable_round_trip() {
+        let testcase = local("Small numbers");
+        assert_eq!(LocalTestcase::from_editable(&testcase.to_editable()).unwrap(), testcase);
+        assert!(LocalTestcase::from_editable("===== TITLE ======\nno input").is_err());
+        assert!(LocalTestcase::from_editable("title first\n===== TITLE ======").is_err());
+    }
+
+    #[test]
+    fn test_save_merge_and_remove() {
+        let dir = std::env::temp_dir().join(format!("coctus-local-tests-{}", std::process::id()));
+        let handle = PublicHandle::from_str("abc123").unwrap();
+        let mut locals = LocalTestcases::load(&dir, &handle).unwrap();
+        locals.push(local("First"));
+        locals.push(local("Second"));
+        locals.save().unwrap();
+
+        let mut locals = LocalTestcases::load(&dir, &handle).unwrap();
+        let example = Testcase {
+            index: 1,
+            title: "Example".to_string(),
+            test_in: "1".to_string(),
+            test_out: "1".to_string(),
+            is_validator: false,
+        };
+        let merged = locals.merged_with(&[example]);
+        let titles: Vec<(usize, &str)> = merged.iter().map(|t| (t.index, t.title.as_str())).collect();
+        assert_eq!(titles, [(1, "Example"), (2, "[local] First"), (3, "[local] Second")]);
+
+        assert!(locals.remove(1, 1).is_err());
+        assert!(locals.remove(4, 1).is_err());
+        assert_eq!(locals.remove(2, 1).unwrap().title, "First");
+        locals.get_mut(2, 1).unwrap().title = "Renamed".to_string();
+        locals.save().unwrap();
+        assert_eq!(
+            LocalTestcases::load(&dir, &handle).unwrap().testcases,
+            [LocalTestcase {
+                title: "Renamed".to_string(),
+                ..local("")
+            }]
+        );
+
+        locals.remove(2, 1).unwrap();
+        locals.save().unwrap();
+        assert!(!dir.join("abc123.json").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,6 +1,8 @@
+use std::ops::Range;
+
 use ansi_term::{Color, Style};
 use clashlib::clash::{Clash, Testcase};
-use clashlib::solution::TestResult;
+use clashlib::solution::{Comparator, Output, ResourceUsage, TestResult, TimingStats};
 
 use super::formatter::show_whitespace;
 use super::lines_with_endings::LinesWithEndings;
@@ -160,7 +162,24 @@ impl OutputStyle {
         self.print_testcases(clash, selection);
     }
 
-    fn print_diff(&self, testcase: &Testcase, stdout: &str) {
+    // `invalid` are the byte ranges of `stdout` where invalid UTF-8 was
+    // replaced, they are highlighted wherever they are shown.
+    fn print_diff(
+        &self,
+        testcase: &Testcase,
+        stdout: &str,
+        invalid: &[Range<usize>],
+        comparator: &Comparator,
+    ) {
+        if stdout.is_empty() {
+            println!("{}", self.dim_color.paint("(no output)"));
+            return
+        }
+        if *comparator != Comparator::Exact {
+            self.print_annotated_output(testcase, stdout, invalid, comparator);
+            return
+        }
+
         use dissimilar::Chunk::*;
         use itertools::EitherOrBoth::{Both, Left, Right};
         use itertools::Itertools;
@@ -170,19 +189,23 @@ impl OutputStyle {
         let diff_green = &self.diff_green;
         let diff_ws_green = &self.diff_green_whitespace;
 
-        if stdout.is_empty() {
-            println!("{}", self.dim_color.paint("(no output)"));
-            return
-        }
-
         let expected_lines = LinesWithEndings::from(&testcase.test_out);
         let actual_lines = LinesWithEndings::from(stdout);
 
+        // Parts of the output are printed in order, `printed` is where the
+        // next one starts
+        let mut printed = 0;
+        let mut show = |text: &str, style: &Style, ws_style: &Option<Style>| {
+            let shown = self.show_output_part(text, printed, invalid, style, ws_style);
+            printed += text.len();
+            shown
+        };
+
         let mut missing_lines = 0;
         for either_or_both in expected_lines.zip_longest(actual_lines) {
             match either_or_both {
                 Left(_) => missing_lines += 1,
-                Right(s) => print!("{}", show_whitespace(s, diff_red, diff_ws_red)),
+                Right(s) => print!("{}", show(s, diff_red, diff_ws_red)),
                 Both(a, b) => {
                     let mut prev_deleted = false;
 
@@ -192,13 +215,13 @@ impl OutputStyle {
                                 let mut chars = text.chars();
                                 let first_char = chars.next().expect("diff chunk should not be empty");
                                 let rest = chars.as_str();
-                                print!("{}", show_whitespace(&first_char.to_string(), diff_red, diff_ws_red));
+                                print!("{}", show(&first_char.to_string(), diff_red, diff_ws_red));
                                 if !rest.is_empty() {
-                                    print!("{}", show_whitespace(rest, diff_green, diff_ws_green));
+                                    print!("{}", show(rest, diff_green, diff_ws_green));
                                 }
                             }
-                            Equal(text) => print!("{}", show_whitespace(text, diff_green, diff_ws_green)),
-                            Insert(text) => print!("{}", show_whitespace(text, diff_red, diff_ws_red)),
+                            Equal(text) => print!("{}", show(text, diff_green, diff_ws_green)),
+                            Insert(text) => print!("{}", show(text, diff_red, diff_ws_red)),
                             Delete(_) => {}
                         }
 
@@ -218,10 +241,124 @@ impl OutputStyle {
         }
     }
 
-    pub fn print_result(&self, testcase: &Testcase, test_result: &TestResult) {
-        let title = self.styled_testcase_title(testcase);
+    // Highlights only the tokens of the output that the comparator did not
+    // accept.
+    fn print_annotated_output(
+        &self,
+        testcase: &Testcase,
+        stdout: &str,
+        invalid: &[Range<usize>],
+        comparator: &Comparator,
+    ) {
+        let annotation = comparator.annotate(&testcase.test_out, stdout);
+        let green = |range: Range<usize>| {
+            let text = &stdout[range.clone()];
+            self.show_output_part(text, range.start, invalid, &self.diff_green, &self.diff_green_whitespace)
+        };
+        let red = |range: Range<usize>| {
+            let text = &stdout[range.clone()];
+            self.show_output_part(text, range.start, invalid, &self.diff_red, &self.diff_red_whitespace)
+        };
+
+        let mut printed = 0;
+        for token in &annotation.tokens {
+            print!("{}", green(printed..token.range.start));
+            let range = token.range.clone();
+            print!("{}", if token.matches { green(range) } else { red(range) });
+            printed = token.range.end;
+        }
+        print!("{}", green(printed..stdout.len()));
+
+        if !stdout.ends_with('\n') {
+            println!()
+        }
+
+        if annotation.missing > 0 {
+            let unit = match comparator {
+                Comparator::Exact | Comparator::UnorderedLines | Comparator::CaseInsensitive => "lines",
+                Comparator::Tokens | Comparator::Float { .. } => "tokens",
+            };
+            let msg = format!("(expected {} more {unit})", annotation.missing);
+            println!("{}", self.dim_color.paint(msg));
+        }
+    }
+
+    // `text` is the part of the output that starts at byte `offset`. The
+    // characters that replaced invalid UTF-8 are highlighted as errors.
+    fn show_output_part(
+        &self,
+        text: &str,
+        offset: usize,
+        invalid: &[Range<usize>],
+        style: &Style,
+        ws_style: &Option<Style>,
+    ) -> String {
+        let end = offset + text.len();
+        let mut shown = String::new();
+        let mut start = 0;
+        for range in invalid.iter().filter(|range| range.start < end && range.end > offset) {
+            let (from, to) = (range.start.max(offset) - offset, range.end.min(end) - offset);
+            shown += &show_whitespace(&text[start..from], style, ws_style);
+            shown += &self.error.paint(&text[from..to]).to_string();
+            start = to;
+        }
+        shown += &show_whitespace(&text[start..], style, ws_style);
+        shown
+    }
+
+    pub fn styled_usage(&self, usage: &ResourceUsage) -> String {
+        self.dim_color.paint(format!("({usage})")).to_string()
+    }
+
+    pub fn print_timing_stats(&self, title: &str, stats: &TimingStats) {
+        println!(
+            "{title}\n  min {:.3}s  median {:.3}s  p95 {:.3}s {}",
+            stats.min.as_secs_f64(),
+            stats.median.as_secs_f64(),
+            stats.p95.as_secs_f64(),
+            self.dim_color.paint(format!("({} runs)", stats.runs)),
+        );
+    }
+
+    /// Print the lines of `new` with lines removed from `old` prefixed by `-`
+    /// and lines added prefixed by `+`.
+    pub fn print_source_diff(&self, old: &str, new: &str) {
+        let old: Vec<&str> = old.lines().collect();
+        let new: Vec<&str> = new.lines().collect();
+
+        // Length of the longest common subsequence of old[i..] and new[j..]
+        let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                common[i][j] = match old[i] == new[j] {
+                    true => common[i + 1][j + 1] + 1,
+                    false => common[i + 1][j].max(common[i][j + 1]),
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < old.len() || j < new.len() {
+            if i < old.len() && j < new.len() && old[i] == new[j] {
+                println!("  {}", old[i]);
+                (i, j) = (i + 1, j + 1);
+            } else if j == new.len() || (i < old.len() && common[i + 1][j] >= common[i][j + 1]) {
+                println!("{}", self.diff_red.paint(format!("- {}", old[i])));
+                i += 1;
+            } else {
+                println!("{}", self.diff_green.paint(format!("+ {}", new[j])));
+                j += 1;
+            }
+        }
+    }
+
+    pub fn print_result(&self, testcase: &Testcase, test_result: &TestResult, comparator: &Comparator) {
+        let title = match test_result.usage() {
+            Some(usage) => format!("{} {}", self.styled_testcase_title(testcase), self.styled_usage(usage)),
+            None => self.styled_testcase_title(testcase),
+        };
         match test_result {
-            TestResult::Success => {
+            TestResult::Success { .. } => {
                 println!("{} {}", self.success.paint("PASS"), title);
             }
 
@@ -230,24 +367,66 @@ impl OutputStyle {
                 println!(" {}", self.stderr.paint(error_msg));
             }
 
-            TestResult::WrongOutput { stdout, stderr } => {
+            TestResult::CheckerFailed { error_msg, .. } => {
+                println!("{} {}", self.failure.paint("CHECKER ERROR"), title);
+                println!(" {}", self.stderr.paint(error_msg));
+            }
+
+            TestResult::WrongOutput {
+                stdout,
+                stderr,
+                message,
+                ..
+            } => {
                 println!("{} {}", self.failure.paint("FAIL"), title);
-                self.print_failure(testcase, stdout, stderr);
+                self.print_failure(testcase, stdout, stderr, comparator);
+                if let Some(message) = message {
+                    println!("{}\n{}", self.secondary_title.paint("==== CHECKER ====="), message);
+                }
             }
 
-            TestResult::RuntimeError { stdout, stderr } => {
+            TestResult::RuntimeError {
+                stdout, stderr, exit, ..
+            } => {
                 println!("{} {}", self.error.paint("ERROR"), title);
-                self.print_failure(testcase, stdout, stderr);
+                println!(" {}", self.stderr.paint(format!("Solution {exit}")));
+                self.print_failure(testcase, stdout, stderr, comparator);
             }
 
-            TestResult::Timeout { stdout, stderr } => {
+            TestResult::Timeout { stdout, stderr, .. } => {
                 println!("{} {}", self.error.paint("TIMEOUT"), title);
-                self.print_failure(testcase, stdout, stderr);
+                self.print_failure(testcase, stdout, stderr, comparator);
+            }
+
+            TestResult::MemoryLimitExceeded { stdout, stderr, .. } => {
+                println!("{} {}", self.error.paint("MEMORY LIMIT EXCEEDED"), title);
+                self.print_failure(testcase, stdout, stderr, comparator);
+            }
+
+            TestResult::CpuLimitExceeded { stdout, stderr, .. } => {
+                println!("{} {}", self.error.paint("CPU LIMIT EXCEEDED"), title);
+                self.print_failure(testcase, stdout, stderr, comparator);
+            }
+
+            TestResult::OutputLimitExceeded { stdout, stderr, .. } => {
+                println!("{} {}", self.error.paint("OUTPUT LIMIT EXCEEDED"), title);
+                self.print_failure(testcase, stdout, stderr, comparator);
+                println!("{}", self.dim_color.paint("(output was truncated)"));
             }
         }
     }
 
-    fn print_failure(&self, testcase: &Testcase, stdout: &str, stderr: &str) {
+    /// Print only whether the testcase passed, like CodinGame does for
+    /// validators.
+    pub fn print_hidden_result(&self, testcase: &Testcase, test_result: &TestResult) {
+        let title = self.styled_testcase_title(testcase);
+        match test_result.is_success() {
+            true => println!("{} {}", self.success.paint("PASS"), title),
+            false => println!("{} {}", self.failure.paint("FAIL"), title),
+        }
+    }
+
+    fn print_failure(&self, testcase: &Testcase, stdout: &Output, stderr: &Output, comparator: &Comparator) {
         println!(
             "{}\n{}\n{}\n{}",
             self.secondary_title.paint("===== INPUT ======"),
@@ -257,13 +436,33 @@ impl OutputStyle {
         );
 
         println!("{}", &self.secondary_title.paint("===== STDOUT ====="));
-        self.print_diff(testcase, stdout);
+        // Output that is not valid UTF-8 is diffed with the invalid bytes
+        // replaced by U+FFFD, one replacement character per invalid sequence.
+        let invalid_bytes = stdout.invalid_utf8();
+        let replacement_len = char::REPLACEMENT_CHARACTER.len_utf8();
+        let mut replaced: Vec<Range<usize>> = Vec::new();
+        let mut removed = 0;
+        for range in &invalid_bytes {
+            let start = range.start - removed + replaced.len() * replacement_len;
+            replaced.push(start..start + replacement_len);
+            removed += range.len();
+        }
+        self.print_diff(testcase, &stdout.to_str_lossy(), &replaced, comparator);
+        if !invalid_bytes.is_empty() {
+            let offsets: Vec<String> = invalid_bytes.iter().map(|range| range.start.to_string()).collect();
+            let plural = if offsets.len() == 1 { "" } else { "s" };
+            let msg = format!(
+                "(output is not valid UTF-8, invalid bytes at offset{plural} {})",
+                offsets.join(", ")
+            );
+            println!("{}", self.stderr.paint(msg));
+        }
 
         if !stderr.is_empty() {
             println!(
                 "{}\n{}",
                 self.secondary_title.paint("===== STDERR ====="),
-                self.stderr.paint(stderr.trim_end())
+                self.stderr.paint(stderr.to_str_lossy().trim_end())
             );
         }
     }
@@ -0,0 +1,396 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+use clashlib::clash::{PublicHandle, Testcase};
+use clashlib::solution::{ResourceUsage, TestResult};
+use serde::Serialize;
+
+use super::history::TestcaseOutcome;
+
+/// Format of the results printed by `coctus run`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    /// Human readable, colored output with diffs.
+    Text,
+    Json,
+    /// JUnit XML, understood by most CI systems.
+    Junit,
+    /// Test Anything Protocol version 13.
+    Tap,
+}
+
+/// Machine readable summary of a run of a solution against the testcases of
+/// a clash.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    clash: String,
+    testcases: Vec<TestcaseRecord>,
+}
+
+#[derive(Debug, Serialize)]
+struct TestcaseRecord {
+    index: usize,
+    title: String,
+    validator: bool,
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timings: Option<Timings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stdout: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stderr: Option<String>,
+    /// Explanation of the status, for example the exit code of a solution
+    /// that crashed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
+
+/// [ResourceUsage] with times in seconds and memory in bytes.
+#[derive(Debug, Serialize)]
+struct Timings {
+    wall_time: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_time: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_time: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_rss: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Summary {
+    total: usize,
+    passed: usize,
+    failed: usize,
+    skipped: usize,
+    wall_time: f64,
+}
+
+const PASS: &str = "pass";
+const WRONG_OUTPUT: &str = "wrong_output";
+const SKIPPED: &str = "skipped";
+
+fn status(test_result: &TestResult) -> &'static str {
+    match test_result {
+        TestResult::Success { .. } => PASS,
+        TestResult::WrongOutput { .. } => WRONG_OUTPUT,
+        TestResult::UnableToRun { .. } => "unable_to_run",
+        TestResult::CheckerFailed { .. } => "checker_failed",
+        TestResult::RuntimeError { .. } => "runtime_error",
+        TestResult::Timeout { .. } => "timeout",
+        TestResult::MemoryLimitExceeded { .. } => "memory_limit_exceeded",
+        TestResult::CpuLimitExceeded { .. } => "cpu_limit_exceeded",
+        TestResult::OutputLimitExceeded { .. } => "output_limit_exceeded",
+    }
+}
+
+fn message(test_result: &TestResult) -> Option<String> {
+    match test_result {
+        TestResult::UnableToRun { error_msg } | TestResult::CheckerFailed { error_msg, .. } => {
+            Some(error_msg.clone())
+        }
+        TestResult::WrongOutput { message, .. } => message.clone(),
+        TestResult::RuntimeError { exit, .. } => Some(format!("Solution {exit}")),
+        TestResult::OutputLimitExceeded { .. } => Some(String::from("Output was truncated")),
+        _ => None,
+    }
+}
+
+/// The score CodinGame gives a solution: the percentage of validators that
+/// pass, rounded down. `None` if there are no validators.
+pub fn codingame_score(validators_passed: usize, validators_total: usize) -> Option<usize> {
+    (validators_total > 0).then(|| validators_passed * 100 / validators_total)
+}
+
+impl From<&ResourceUsage> for Timings {
+    fn from(usage: &ResourceUsage) -> Self {
+        Timings {
+            wall_time: usage.wall_time.as_secs_f64(),
+            user_time: usage.user_time.map(|time| time.as_secs_f64()),
+            system_time: usage.system_time.map(|time| time.as_secs_f64()),
+            max_rss: usage.max_rss,
+        }
+    }
+}
+
+impl Report {
+    pub fn new(handle: &PublicHandle) -> Self {
+        Report {
+            clash: handle.to_string(),
+            testcases: Vec::new(),
+        }
+    }
+
+    /// Number of testcases in the report (including skipped ones).
+    pub fn len(&self) -> usize {
+        self.testcases.len()
+    }
+
+    pub fn add(&mut self, testcase: &Testcase, test_result: &TestResult) {
+        self.testcases.push(TestcaseRecord {
+            index: testcase.index,
+            title: testcase.title.clone(),
+            validator: testcase.is_validator,
+            status: status(test_result),
+            timings: test_result.usage().map(Timings::from),
+            stdout: test_result.stdout().map(|stdout| stdout.to_str_lossy().into_owned()),
+            stderr: test_result.stderr().map(|stderr| stderr.to_str_lossy().into_owned()),
+            message: message(test_result),
+        });
+    }
+
+    /// Record a testcase that was not run because an earlier one failed.
+    pub fn skip(&mut self, testcase: &Testcase) {
+        self.testcases.push(TestcaseRecord {
+            index: testcase.index,
+            title: testcase.title.clone(),
+            validator: testcase.is_validator,
+            status: SKIPPED,
+            timings: None,
+            stdout: None,
+            stderr: None,
+            message: None,
+        });
+    }
+
+    /// The status of each testcase, for the run history.
+    pub fn testcase_outcomes(&self) -> Vec<TestcaseOutcome> {
+        self.testcases
+            .iter()
+            .map(|record| TestcaseOutcome {
+                index: record.index,
+                status: record.status.to_string(),
+            })
+            .collect()
+    }
+
+    fn summary(&self) -> Summary {
+        let mut summary = Summary {
+            total: self.testcases.len(),
+            ..Default::default()
+        };
+        for record in &self.testcases {
+            match record.status {
+                PASS => summary.passed += 1,
+                SKIPPED => summary.skipped += 1,
+                _ => summary.failed += 1,
+            }
+            summary.wall_time += record.timings.as_ref().map_or(0.0, |timings| timings.wall_time);
+        }
+        summary
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        #[derive(Serialize)]
+        struct JsonReport<'a> {
+            #[serde(flatten)]
+            report: &'a Report,
+            summary: Summary,
+        }
+        let report = JsonReport {
+            report: self,
+            summary: self.summary(),
+        };
+        Ok(serde_json::to_string_pretty(&report)?)
+    }
+
+    pub fn to_junit(&self) -> String {
+        let summary = self.summary();
+        let num_failures = self.testcases.iter().filter(|record| record.status == WRONG_OUTPUT).count();
+        let num_errors = summary.failed - num_failures;
+        let counts = format!(
+            r#"tests="{}" failures="{num_failures}" errors="{num_errors}" skipped="{}" time="{:.3}""#,
+            summary.total, summary.skipped, summary.wall_time
+        );
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        writeln!(xml, "<testsuites {counts}>").unwrap();
+        writeln!(xml, r#"  <testsuite name="{}" {counts}>"#, self.clash).unwrap();
+        for record in &self.testcases {
+            let wall_time = record.timings.as_ref().map_or(0.0, |timings| timings.wall_time);
+            writeln!(
+                xml,
+                r#"    <testcase name="{}" classname="coctus.{}" time="{wall_time:.3}">"#,
+                xml_escape(&format!("#{} {}", record.index, record.title)),
+                self.clash,
+            )
+            .unwrap();
+            writeln!(
+                xml,
+                r#"      <properties><property name="validator" value="{}"/></properties>"#,
+                record.validator
+            )
+            .unwrap();
+            let element = match record.status {
+                PASS => None,
+                SKIPPED => Some("skipped"),
+                WRONG_OUTPUT => Some("failure"),
+                _ => Some("error"),
+            };
+            if let Some(element) = element {
+                let message = record.status.replace('_', " ");
+                match &record.message {
+                    Some(text) => writeln!(
+                        xml,
+                        r#"      <{element} type="{}" message="{message}">{}</{element}>"#,
+                        record.status,
+                        xml_escape(text)
+                    ),
+                    None => {
+                        writeln!(xml, r#"      <{element} type="{}" message="{message}"/>"#, record.status)
+                    }
+                }
+                .unwrap();
+            }
+            if let Some(stdout) = &record.stdout {
+                writeln!(xml, "      <system-out>{}</system-out>", xml_escape(stdout)).unwrap();
+            }
+            if let Some(stderr) = record.stderr.as_ref().filter(|stderr| !stderr.is_empty()) {
+                writeln!(xml, "      <system-err>{}</system-err>", xml_escape(stderr)).unwrap();
+            }
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n</testsuites>");
+        xml
+    }
+
+    pub fn to_tap(&self) -> String {
+        let mut tap = format!("TAP version 13\n1..{}\n", self.testcases.len());
+        for (number, record) in (1..).zip(&self.testcases) {
+            let description = record.title.replace('\\', "\\\\").replace('#', "\\#");
+            match record.status {
+                PASS => writeln!(tap, "ok {number} - {description}"),
+                SKIPPED => writeln!(tap, "ok {number} - {description} # SKIP not run"),
+                _ => writeln!(tap, "not ok {number} - {description}"),
+            }
+            .unwrap();
+            if matches!(record.status, PASS | SKIPPED) {
+                continue
+            }
+
+            // The YAML diagnostics block. JSON strings are valid YAML scalars
+            // and take care of escaping.
+            let yaml_string = |text: &str| serde_json::to_string(text).expect("strings should serialize");
+            tap.push_str("  ---\n");
+            writeln!(tap, "  index: {}", record.index).unwrap();
+            writeln!(tap, "  validator: {}", record.validator).unwrap();
+            writeln!(tap, "  status: {}", record.status).unwrap();
+            if let Some(timings) = &record.timings {
+                writeln!(tap, "  wall_time: {:.3}", timings.wall_time).unwrap();
+            }
+            if let Some(message) = &record.message {
+                writeln!(tap, "  message: {}", yaml_string(message)).unwrap();
+            }
+            if let Some(stdout) = &record.stdout {
+                writeln!(tap, "  stdout: {}", yaml_string(stdout)).unwrap();
+            }
+            if let Some(stderr) = &record.stderr {
+                writeln!(tap, "  stderr: {}", yaml_string(stderr)).unwrap();
+            }
+            tap.push_str("  ...\n");
+        }
+        tap.truncate(tap.trim_end().len());
+        tap
+    }
+}
+
+// Escapes text for use in XML attributes and elements. Characters that are
+// not allowed in XML 1.0 at all are replaced.
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(ch),
+            '\u{0}'..='\u{1F}' | '\u{FFFE}' | '\u{FFFF}' => escaped.push(char::REPLACEMENT_CHARACTER),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn testcase(index: usize, title: &str) -> Testcase {
+        Testcase {
+            index,
+            title: String::from(title),
+            test_in: String::new(),
+            test_out: String::from("ok"),
+            is_validator: index % 2 == 0,
+        }
+    }
+
+    fn sample_report() -> Report {
+        let mut report = Report::new(&PublicHandle::from_str("abc123").unwrap());
+        report.add(
+            &testcase(1, "Simple"),
+            &TestResult::Success {
+                usage: ResourceUsage::default(),
+            },
+        );
+        report.add(
+            &testcase(2, "A <b> & #2"),
+            &TestResult::WrongOutput {
+                stdout: b"nope\x01".to_vec().into(),
+                stderr: Vec::new().into(),
+                message: None,
+                usage: ResourceUsage::default(),
+            },
+        );
+        report.skip(&testcase(3, "Big"));
+        report
+    }
+
+    #[test]
+    fn test_summary() {
+        let summary = sample_report().summary();
+        assert_eq!((summary.total, summary.passed, summary.failed, summary.skipped), (3, 1, 1, 1));
+    }
+
+    #[test]
+    fn test_codingame_score_rounds_down() {
+        assert_eq!(codingame_score(2, 3), Some(66));
+        assert_eq!(codingame_score(4, 4), Some(100));
+        assert_eq!(codingame_score(0, 5), Some(0));
+        assert_eq!(codingame_score(0, 0), None);
+    }
+
+    #[test]
+    fn test_json_report() {
+        let json: serde_json::Value = serde_json::from_str(&sample_report().to_json().unwrap()).unwrap();
+        assert_eq!(json["clash"], "abc123");
+        assert_eq!(json["testcases"][1]["status"], "wrong_output");
+        assert_eq!(json["testcases"][1]["validator"], true);
+        assert_eq!(json["testcases"][1]["stdout"], "nope\u{1}");
+        assert_eq!(json["summary"]["failed"], 1);
+    }
+
+    #[test]
+    fn test_junit_report_is_escaped() {
+        let xml = sample_report().to_junit();
+        assert!(xml.contains(r##"name="#2 A &lt;b&gt; &amp; #2""##));
+        assert!(xml.contains("<system-out>nope\u{FFFD}</system-out>"));
+        assert!(xml.contains(r#"<failure type="wrong_output" message="wrong output"/>"#));
+        assert!(xml.contains("<skipped"));
+        assert!(xml.contains(r#"tests="3" failures="1" errors="0" skipped="1""#));
+    }
+
+    #[test]
+    fn test_tap_report() {
+        let tap = sample_report().to_tap();
+        let lines: Vec<&str> = tap.lines().collect();
+        assert_eq!(lines[..3], ["TAP version 13", "1..3", "ok 1 - Simple"]);
+        assert_eq!(lines[3], r"not ok 2 - A <b> & \#2");
+        assert!(lines.contains(&r#"  stdout: "nope\u0001""#));
+        assert_eq!(lines.last(), Some(&"ok 3 - Big # SKIP not run"));
+    }
+}
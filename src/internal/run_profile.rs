@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+
+const BUILTIN_RUN_PROFILES: &str = include_str!("../../config/run_profiles.toml");
+
+/// Commands for building and running a solution written in some language.
+///
+/// The commands may contain the placeholders `{source}`, `{source_dir}` and
+/// `{build_dir}`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RunProfile {
+    #[serde(default)]
+    build: Option<String>,
+    run: String,
+}
+
+/// Run profiles keyed by language name.
+#[derive(Debug, Default, Deserialize)]
+pub struct RunProfiles(BTreeMap<String, RunProfile>);
+
+/// The commands for building and running a solution.
+pub struct SolutionCommands {
+    pub build: Option<Command>,
+    pub run: Command,
+    /// Files the build depends on. Builds are only cached when there are
+    /// some.
+    pub sources: Vec<PathBuf>,
+    /// Directory the build command puts its artifacts in (if known).
+    pub build_dir: Option<PathBuf>,
+}
+
+impl SolutionCommands {
+    pub fn new(build: Option<Command>, run: Command, sources: Vec<PathBuf>) -> Self {
+        SolutionCommands {
+            build,
+            run,
+            sources,
+            build_dir: None,
+        }
+    }
+
+    /// Whether the artifacts of a previous build may still exist. Temporary
+    /// build directories may have been cleaned up since the last build.
+    pub fn has_build_artifacts(&self) -> bool {
+        match &self.build_dir {
+            Some(dir) => std::fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_some()),
+            None => true,
+        }
+    }
+}
+
+impl RunProfiles {
+    /// The built-in profiles, overridden by the ones in `user_file` (if it
+    /// exists).
+    pub fn load(user_file: &Path) -> Result<Self> {
+        let mut profiles: RunProfiles =
+            toml::from_str(BUILTIN_RUN_PROFILES).expect("built-in run profiles should be valid");
+        match std::fs::read_to_string(user_file) {
+            Ok(contents) => {
+                let user_profiles: RunProfiles = toml::from_str(&contents)
+                    .with_context(|| format!("Unable to parse run profiles from {:?}", user_file))?;
+                profiles.0.extend(user_profiles.0);
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err).with_context(|| format!("Unable to read {:?}", user_file)),
+        }
+        Ok(profiles)
+    }
+
+    pub fn get(&self, language: &str) -> Result<&RunProfile> {
+        self.0.get(language).with_context(|| {
+            let available: Vec<&str> = self.0.keys().map(String::as_str).collect();
+            format!("No run profile for '{language}' (available: {})", available.join(", "))
+        })
+    }
+}
+
+impl RunProfile {
+    /// Fill in the commands for running `source`. Profiles with a build
+    /// command get a build directory inside `builds_dir` that is reused by
+    /// later runs of the same profile and source so the build can be cached.
+    pub fn commands(&self, source: &Path, builds_dir: &Path) -> Result<SolutionCommands> {
+        let source = source
+            .canonicalize()
+            .with_context(|| format!("Unable to find solution source {:?}", source))?;
+        let build_dir = match &self.build {
+            Some(build) => Some(create_build_dir(builds_dir, build, &source)?),
+            None => None,
+        };
+        let placeholders = Placeholders {
+            source: &source,
+            source_dir: source.parent().expect("canonicalized file path should have a parent"),
+            build_dir: build_dir.as_deref(),
+        };
+        Ok(SolutionCommands {
+            build: self.build.as_deref().map(|build| placeholders.command(build)).transpose()?,
+            run: placeholders.command(&self.run)?,
+            sources: vec![source.clone()],
+            build_dir,
+        })
+    }
+}
+
+struct Placeholders<'a> {
+    source: &'a Path,
+    source_dir: &'a Path,
+    build_dir: Option<&'a Path>,
+}
+
+impl Placeholders<'_> {
+    // The template is split into words before the placeholders are replaced
+    // so paths with spaces in them stay in one argument.
+    fn command(&self, template: &str) -> Result<Command> {
+        let words = shlex::split(template)
+            .filter(|words| !words.is_empty())
+            .with_context(|| format!("Invalid command in run profile: {template}"))?;
+        let mut words = words.iter().map(|word| self.fill(word));
+        let mut command = Command::new(words.next().expect("command should not be empty")?);
+        for word in words {
+            command.arg(word?);
+        }
+        Ok(command)
+    }
+
+    fn fill(&self, word: &str) -> Result<String> {
+        let mut filled = word
+            .replace("{source}", &self.source.to_string_lossy())
+            .replace("{source_dir}", &self.source_dir.to_string_lossy());
+        if filled.contains("{build_dir}") {
+            let build_dir = self
+                .build_dir
+                .ok_or_else(|| anyhow!("{{build_dir}} can only be used in profiles with a build command"))?;
+            filled = filled.replace("{build_dir}", &build_dir.to_string_lossy());
+        }
+        Ok(filled)
+    }
+}
+
+/// Directory inside `builds_dir` for the artifacts of building `source` with
+/// the `build` command template.
+fn create_build_dir(builds_dir: &Path, build: &str, source: &Path) -> Result<PathBuf> {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    hasher.update(build.as_bytes());
+    hasher.update([0]);
+    hasher.update(source.to_string_lossy().as_bytes());
+    let digest: String = hasher.finalize()[..8].iter().map(|byte| format!("{byte:02x}")).collect();
+    let dir = builds_dir.join(digest);
+    std::fs::create_dir_all(&dir).with_context(|| format!("Unable to create build directory {:?}", dir))?;
+    Ok(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_profiles_cover_stub_languages() {
+        let profiles = RunProfiles::load(Path::new("/nonexistent/run_profiles.toml")).unwrap();
+        for language in ["c", "cpp", "pascal", "python", "ruby", "rust"] {
+            assert!(profiles.get(language).is_ok(), "missing run profile for {language}");
+        }
+        assert!(profiles.get("brainfuck").is_err());
+    }
+
+    #[test]
+    fn test_placeholders_are_filled_per_argument() {
+        let placeholders = Placeholders {
+            source: Path::new("/my solutions/sol.rs"),
+            source_dir: Path::new("/my solutions"),
+            build_dir: Some(Path::new("/tmp/build")),
+        };
+        let command = placeholders.command("rustc -o {build_dir}/solution {source}").unwrap();
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(args, ["-o", "/tmp/build/solution", "/my solutions/sol.rs"]);
+    }
+
+    #[test]
+    fn test_build_dir_requires_build_command() {
+        let placeholders = Placeholders {
+            source: Path::new("/sol.py"),
+            source_dir: Path::new("/"),
+            build_dir: None,
+        };
+        assert!(placeholders.command("{build_dir}/solution").is_err());
+    }
+}
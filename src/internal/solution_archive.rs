@@ -0,0 +1,206 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use clashlib::clash::PublicHandle;
+
+/// Saved solutions, stored as `DIR/HANDLE/LANGUAGE/VERSION[.EXT]`. Versions
+/// of a language are numbered from 1 in the order they were saved.
+pub struct SolutionArchive {
+    dir: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchivedSolution {
+    pub language: String,
+    pub version: u32,
+    pub path: PathBuf,
+}
+
+/// Reference to an archived solution on the command line: `LANGUAGE` for the
+/// latest version or `LANGUAGE:VERSION`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolutionRef {
+    pub language: String,
+    pub version: Option<u32>,
+}
+
+impl SolutionArchive {
+    pub fn new(dir: &Path) -> Self {
+        SolutionArchive { dir: dir.to_owned() }
+    }
+
+    /// Save a copy of `source` as the next version of `language`. Returns the
+    /// archived solution and whether it is new, a source identical to an
+    /// already archived version is not saved again.
+    pub fn save(
+        &self,
+        handle: &PublicHandle,
+        language: &str,
+        source: &Path,
+    ) -> Result<(ArchivedSolution, bool)> {
+        if language.is_empty() || language.contains(['/', '\\', ':']) || language.starts_with('.') {
+            return Err(anyhow!("Invalid language name '{language}'"))
+        }
+        let contents =
+            std::fs::read(source).with_context(|| format!("Unable to read solution source {:?}", source))?;
+        let versions = self.versions(handle, language)?;
+        for archived in &versions {
+            if std::fs::read(&archived.path).is_ok_and(|archived_contents| archived_contents == contents) {
+                return Ok((archived.clone(), false))
+            }
+        }
+
+        let version = versions.last().map_or(1, |latest| latest.version + 1);
+        let mut file_name = version.to_string();
+        if let Some(ext) = source.extension() {
+            file_name = format!("{file_name}.{}", ext.to_string_lossy());
+        }
+        let language_dir = self.dir.join(handle.to_string()).join(language);
+        std::fs::create_dir_all(&language_dir)
+            .with_context(|| format!("Unable to create directory {:?}", language_dir))?;
+        let path = language_dir.join(file_name);
+        std::fs::write(&path, contents).with_context(|| format!("Unable to write {:?}", path))?;
+        let archived = ArchivedSolution {
+            language: language.to_string(),
+            version,
+            path,
+        };
+        Ok((archived, true))
+    }
+
+    /// All archived solutions of a clash, sorted by language and version.
+    pub fn list(&self, handle: &PublicHandle) -> Result<Vec<ArchivedSolution>> {
+        let clash_dir = self.dir.join(handle.to_string());
+        let mut solutions = Vec::new();
+        for language in sorted_file_names(&clash_dir)? {
+            solutions.extend(self.versions(handle, &language)?);
+        }
+        Ok(solutions)
+    }
+
+    pub fn get(&self, handle: &PublicHandle, solution: &SolutionRef) -> Result<ArchivedSolution> {
+        let versions = self.versions(handle, &solution.language)?;
+        let found = match solution.version {
+            Some(version) => versions.into_iter().find(|archived| archived.version == version),
+            None => versions.into_iter().last(),
+        };
+        found.with_context(|| format!("No archived {solution} solution for clash {handle}"))
+    }
+
+    fn versions(&self, handle: &PublicHandle, language: &str) -> Result<Vec<ArchivedSolution>> {
+        let language_dir = self.dir.join(handle.to_string()).join(language);
+        let mut versions: Vec<ArchivedSolution> = sorted_file_names(&language_dir)?
+            .into_iter()
+            .filter_map(|file_name| {
+                let version = file_name.split('.').next()?.parse().ok()?;
+                Some(ArchivedSolution {
+                    language: language.to_string(),
+                    version,
+                    path: language_dir.join(file_name),
+                })
+            })
+            .collect();
+        versions.sort_by_key(|archived| archived.version);
+        Ok(versions)
+    }
+}
+
+// A missing directory has no entries
+fn sorted_file_names(dir: &Path) -> Result<Vec<String>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).with_context(|| format!("Unable to read {:?}", dir)),
+    };
+    let mut names = Vec::new();
+    for entry in entries {
+        let entry = entry.with_context(|| format!("Unable to read {:?}", dir))?;
+        names.push(entry.file_name().to_string_lossy().into_owned());
+    }
+    names.sort();
+    Ok(names)
+}
+
+impl FromStr for SolutionRef {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (language, version) = match s.split_once(':') {
+            Some((language, version)) => {
+                let version = version.parse().map_err(|_| anyhow!("Invalid solution version '{version}'"))?;
+                (language, Some(version))
+            }
+            None => (s, None),
+        };
+        if language.is_empty() {
+            return Err(anyhow!("Missing language in '{s}'"))
+        }
+        Ok(SolutionRef {
+            language: language.to_string(),
+            version,
+        })
+    }
+}
+
+impl std::fmt::Display for SolutionRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.version {
+            Some(version) => write!(f, "{}:{version}", self.language),
+            None => write!(f, "{}", self.language),
+        }
+    }
+}
+
+impl std::fmt::Display for ArchivedSolution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.language, self.version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_get_versions() {
+        let tmp = std::env::temp_dir().join(format!("coctus-archive-test-{}", std::process::id()));
+        let archive = SolutionArchive::new(&tmp.join("solutions"));
+        let handle = PublicHandle::from_str("abc123").unwrap();
+        let source = tmp.join("sol.py");
+        std::fs::create_dir_all(&tmp).unwrap();
+
+        std::fs::write(&source, "print(1)").unwrap();
+        let (first, is_new) = archive.save(&handle, "python", &source).unwrap();
+        assert!(is_new);
+        assert_eq!(first.version, 1);
+        assert!(first.path.ends_with("abc123/python/1.py"));
+        let (same, is_new) = archive.save(&handle, "python", &source).unwrap();
+        assert!(!is_new);
+        assert_eq!(same, first);
+
+        std::fs::write(&source, "print(2)").unwrap();
+        let (second, _) = archive.save(&handle, "python", &source).unwrap();
+        assert_eq!(second.version, 2);
+        archive.save(&handle, "ruby", &source).unwrap();
+
+        let listed: Vec<String> = archive.list(&handle).unwrap().iter().map(ToString::to_string).collect();
+        assert_eq!(listed, ["python:1", "python:2", "ruby:1"]);
+        assert_eq!(archive.get(&handle, &"python".parse().unwrap()).unwrap(), second);
+        assert_eq!(archive.get(&handle, &"python:1".parse().unwrap()).unwrap(), first);
+        assert!(archive.get(&handle, &"python:3".parse().unwrap()).is_err());
+        assert!(archive.save(&handle, "../escape", &source).is_err());
+
+        std::fs::remove_dir_all(&tmp).unwrap();
+    }
+
+    #[test]
+    fn test_parse_solution_ref() {
+        let latest: SolutionRef = "rust".parse().unwrap();
+        assert_eq!(latest.version, None);
+        let second: SolutionRef = "rust:2".parse().unwrap();
+        assert_eq!((second.language.as_str(), second.version), ("rust", Some(2)));
+        assert!("rust:latest".parse::<SolutionRef>().is_err());
+        assert!(":2".parse::<SolutionRef>().is_err());
+    }
+}
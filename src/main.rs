@@ -1,18 +1,24 @@
 mod internal;
 
-use std::io::Read;
+use std::io::{Read, Write};
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
 use std::process::Command;
 use std::str::FromStr;
 
 use anyhow::{anyhow, Context, Result};
 use clap::ArgMatches;
-use clashlib::clash::{Clash, PublicHandle, Testcase};
+use clashlib::clash::{Bounds, Clash, PublicHandle, PuzzleType, Testcase, VariableConstraints};
+use clashlib::solution::{Checker, Comparator, Limits, TimingStats, Verifier};
 use clashlib::stub::StubConfig;
 use clashlib::{solution, stub};
 use directories::ProjectDirs;
-use internal::OutputStyle;
-use rand::seq::IteratorRandom;
+use internal::{
+    Attempt, BuildCache, ClashIndex, ClashSettings, FileWatcher, GolfRecords, History, LocalTestcase,
+    LocalTestcases, Outcome, OutputStyle, Report, ReportFormat, RunProfiles, SolutionArchive,
+    SolutionCommands, SolutionRef,
+};
+use rand::seq::SliceRandom;
 
 fn command_from_argument(cmd_arg: Option<&String>) -> Result<Option<Command>> {
     let cmd = match cmd_arg {
@@ -33,6 +39,284 @@ fn command_from_argument(cmd_arg: Option<&String>) -> Result<Option<Command>> {
     }
 }
 
+fn print_constraints_table(constraints: &[VariableConstraints], ostyle: &OutputStyle) {
+    if constraints.is_empty() {
+        println!("No bounds found in the constraints");
+        return
+    }
+    let rows: Vec<[String; 4]> = constraints
+        .iter()
+        .map(|constraint| {
+            let bounds = |bounds: &Bounds| match bounds.is_empty() {
+                true => String::from("-"),
+                false => bounds.to_string(),
+            };
+            let charset: Vec<String> = constraint.charset.iter().map(ToString::to_string).collect();
+            [
+                constraint.variable.clone(),
+                bounds(&constraint.value),
+                bounds(&constraint.length),
+                if charset.is_empty() {
+                    String::from("-")
+                } else {
+                    charset.join(", ")
+                },
+            ]
+        })
+        .collect();
+    let header = ["VARIABLE", "VALUE", "LENGTH", "CHARACTERS"].map(String::from);
+    let mut widths = [0; 4];
+    for row in std::iter::once(&header).chain(&rows) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let pad = |cell: &str, width: usize| format!("{cell}{}", " ".repeat(width - cell.chars().count()));
+    println!(
+        "{}",
+        ostyle.secondary_title.paint(format!(
+            "{}  {}  {}  {}",
+            pad(&header[0], widths[0]),
+            pad(&header[1], widths[1]),
+            pad(&header[2], widths[2]),
+            header[3]
+        ))
+    );
+    for row in rows {
+        println!(
+            "{}  {}  {}  {}",
+            ostyle.variable.paint(pad(&row[0], widths[0])),
+            pad(&row[1], widths[1]),
+            pad(&row[2], widths[2]),
+            row[3]
+        );
+    }
+}
+
+fn parse_variable_range(arg: &str) -> Result<(String, stub::ValueRange)> {
+    let (variable, range) = arg
+        .split_once('=')
+        .with_context(|| format!("Invalid range '{arg}' (expected VARIABLE=MIN..MAX)"))?;
+    Ok((variable.to_string(), range.parse()?))
+}
+
+/// Random number generator seeded with `--seed`. Without it the seed is
+/// random, it is printed so the same inputs can be generated again.
+fn seeded_rng(args: &ArgMatches) -> rand::rngs::StdRng {
+    use rand::{Rng, SeedableRng};
+
+    let seed = match args.get_one::<u64>("seed") {
+        Some(seed) => *seed,
+        None => {
+            let seed = rand::thread_rng().gen();
+            eprintln!("Seed: {seed}");
+            seed
+        }
+    };
+    rand::rngs::StdRng::seed_from_u64(seed)
+}
+
+/// Let the user edit `text` in their editor ($VISUAL or $EDITOR) and return
+/// the edited text.
+fn edit_in_editor(text: &str) -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| String::from("vi"));
+    let mut command = command_from_argument(Some(&editor))?.context("The editor command is empty")?;
+    // A new file with an unguessable name that only the user can access
+    let (path, mut file) = loop {
+        let path = std::env::temp_dir().join(format!("coctus-testcase-{:016x}.txt", rand::random::<u64>()));
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        match options.open(&path) {
+            Ok(file) => break (path, file),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err).with_context(|| format!("Unable to create {:?}", path)),
+        }
+    };
+    let written = file.write_all(text.as_bytes());
+    drop(file);
+    if let Err(err) = written {
+        let _ = std::fs::remove_file(&path);
+        return Err(err).with_context(|| format!("Unable to write {:?}", path))
+    }
+    let status = command.arg(&path).status();
+    let edited = std::fs::read_to_string(&path).with_context(|| format!("Unable to read {:?}", path));
+    let _ = std::fs::remove_file(&path);
+    match status {
+        Ok(status) if status.success() => edited,
+        Ok(status) => Err(anyhow!("Editor {editor:?} failed ({status})")),
+        Err(err) => Err(err).with_context(|| format!("Unable to run editor {editor:?}")),
+    }
+}
+
+/// Input or output of a testcase from `--NAME` or `--NAME-file`.
+fn testcase_text(args: &ArgMatches, name: &str) -> Result<Option<String>> {
+    if let Some(text) = args.get_one::<String>(name) {
+        return Ok(Some(text.clone()))
+    }
+    match args.get_one::<PathBuf>(&format!("{name}-file")) {
+        Some(path) => {
+            let text = std::fs::read_to_string(path).with_context(|| format!("Unable to read {:?}", path))?;
+            Ok(Some(text.trim_end_matches('\n').to_string()))
+        }
+        None => Ok(None),
+    }
+}
+
+/// The source file of the solution: `--source` or the SOURCE of `--lang`.
+fn solution_source(args: &ArgMatches) -> Option<PathBuf> {
+    let lang_source = args
+        .get_many::<String>("lang")
+        .and_then(|mut values| values.nth(1))
+        .map(PathBuf::from);
+    args.get_one::<PathBuf>("source").cloned().or(lang_source)
+}
+
+/// The LANGUAGE of `--lang`, or the extension of `--source`.
+fn solution_language(args: &ArgMatches) -> Option<String> {
+    if let Some(mut values) = args.get_many::<String>("lang") {
+        return values.next().cloned()
+    }
+    let source = args.get_one::<PathBuf>("source")?;
+    Some(source.extension()?.to_string_lossy().into_owned())
+}
+
+fn limits_from_args(args: &ArgMatches) -> Result<Limits> {
+    // Float literal patterns are a future-compat warning on the MSRV
+    #[allow(clippy::redundant_guards)]
+    let timeout = match *args.get_one::<f64>("timeout").unwrap_or(&5.0) {
+        secs if secs.is_nan() => return Err(anyhow!("Timeout can't be NaN")),
+        secs if secs < 0.0 => return Err(anyhow!("Timeout can't be negative (use 0 for no timeout)")),
+        secs if secs == 0.0 => std::time::Duration::MAX,
+        secs => std::time::Duration::from_micros((secs * 1e6) as u64),
+    };
+
+    let cpu_time = match args.get_one::<f64>("cpu-limit").copied() {
+        Some(secs) if secs.is_nan() || secs <= 0.0 => return Err(anyhow!("CPU limit must be positive")),
+        Some(secs) => Some(std::time::Duration::from_micros((secs * 1e6) as u64)),
+        None => None,
+    };
+    let memory = args.get_one::<u64>("memory-limit").map(|megabytes| megabytes * 1024 * 1024);
+    if cfg!(not(unix)) && (cpu_time.is_some() || memory.is_some()) {
+        return Err(anyhow!("--memory-limit and --cpu-limit are not supported on this platform"))
+    }
+
+    let output = match *args.get_one::<usize>("output-limit").unwrap_or(&0) {
+        0 => None,
+        kilobytes => Some(kilobytes * 1024),
+    };
+
+    Ok(Limits {
+        timeout,
+        memory,
+        cpu_time,
+        output,
+    })
+}
+
+/// `clash_comparator` is used when neither --checker nor --compare is given.
+fn verifier_from_args(args: &ArgMatches, clash_comparator: Option<Comparator>) -> Result<Verifier> {
+    if let Some(comparator) = args.get_one::<Comparator>("compare") {
+        return Ok(Verifier::Compare(*comparator))
+    }
+    let Some(checker) = args.get_one::<String>("checker") else {
+        return Ok(Verifier::Compare(clash_comparator.unwrap_or_default()))
+    };
+    match shlex::split(checker) {
+        Some(mut shlexed_cmd) if !shlexed_cmd.is_empty() => {
+            let program = shlexed_cmd.remove(0);
+            Ok(Verifier::Checker(Checker::new(program, shlexed_cmd)))
+        }
+        _ => Err(anyhow!("Invalid checker COMMAND")),
+    }
+}
+
+fn selected_testcases<'a>(all_testcases: &'a [Testcase], args: &ArgMatches) -> Result<Vec<&'a Testcase>> {
+    match args.get_many::<u64>("testcases") {
+        Some(testcase_indices) => testcase_indices
+            .map(|&idx| {
+                all_testcases.get((idx - 1) as usize).with_context(|| {
+                    format!(
+                        "Invalid testcase index {idx} (the current clash only has {} testcases)",
+                        all_testcases.len()
+                    )
+                })
+            })
+            .collect(),
+        None => Ok(all_testcases.iter().collect()),
+    }
+}
+
+/// Arguments of subcommands that change a local testcase.
+fn local_testcase_args() -> [clap::Arg; 6] {
+    use clap::{arg, value_parser};
+
+    [
+        arg!(--"title" <TITLE> "title of the testcase"),
+        arg!(--"input" <TEXT> "input of the testcase"),
+        arg!(--"input-file" <FILE> "read the input of the testcase from FILE")
+            .value_parser(value_parser!(PathBuf))
+            .conflicts_with("input"),
+        arg!(--"output" <TEXT> "expected output of the testcase"),
+        arg!(--"output-file" <FILE> "read the expected output of the testcase from FILE")
+            .value_parser(value_parser!(PathBuf))
+            .conflicts_with("output"),
+        arg!(--"edit" "open the testcase in an editor even when it is given with the other options"),
+    ]
+}
+
+/// Arguments shared by subcommands that execute a solution.
+fn solution_command_args() -> [clap::Arg; 9] {
+    use clap::{arg, value_parser};
+
+    [
+        arg!(--"build-command" <COMMAND> "command that compiles the solution"),
+        arg!(--"build-sources" <FILE> "files the build depends on, the build is skipped if they are unchanged")
+            .num_args(1..)
+            .action(clap::ArgAction::Set)
+            .value_parser(value_parser!(PathBuf))
+            .requires("build-command"),
+        arg!(--"build-timeout" <SECONDS> "how many seconds before the build is timed out (0 for no timeout)")
+            .value_parser(value_parser!(f64))
+            .default_value("120"),
+        arg!(--"rebuild" "build even if the sources have not changed since the last build"),
+        arg!(--"command" <COMMAND> "command that executes the solution").required_unless_present("lang"),
+        arg!(--"lang" "build and execute SOURCE using the run profile of LANGUAGE")
+            .value_names(["LANGUAGE", "SOURCE"])
+            .num_args(2)
+            .action(clap::ArgAction::Set)
+            .conflicts_with_all(["command", "build-command"]),
+        arg!(--"checker" <COMMAND> "command that decides whether the output of the solution is correct"),
+        arg!(--"compare" <MODE> "how to compare the output to the expected output (default: the comparator set with `coctus compare`, or exact)")
+            .value_parser(Comparator::from_str)
+            .conflicts_with("checker"),
+        arg!(--"testcases" <TESTCASE_INDICES> "indices of the testcases to run (separated by commas)")
+            .value_parser(value_parser!(u64).range(1..99))
+            .value_delimiter(','),
+    ]
+}
+
+/// Arguments for [limits_from_args].
+fn limit_args() -> [clap::Arg; 4] {
+    use clap::{arg, value_parser};
+
+    [
+        arg!(--"timeout" <SECONDS> "how many seconds before execution is timed out (0 for no timeout)")
+            .value_parser(value_parser!(f64))
+            .default_value("5"),
+        arg!(--"memory-limit" <MEGABYTES> "maximum amount of memory the solution may allocate")
+            .value_parser(value_parser!(u64).range(1..)),
+        arg!(--"cpu-limit" <SECONDS> "maximum amount of CPU time the solution may use")
+            .value_parser(value_parser!(f64)),
+        arg!(--"output-limit" <KILOBYTES> "maximum amount of output the solution may print (0 for no limit)")
+            .value_parser(value_parser!(usize))
+            .default_value("16384"),
+    ]
+}
+
 fn cli() -> clap::Command {
     use clap::{arg, value_parser, Command};
 
@@ -56,6 +340,10 @@ fn cli() -> clap::Command {
                         .value_parser(value_parser!(PublicHandle))
                 )
                 .arg(arg!(-'r' --"reverse" "print the clash in reverse mode"))
+                .arg(
+                    arg!(--"constraints" "print the bounds on the variables found in the constraints as a table")
+                        .conflicts_with("reverse")
+                )
         )
         .subcommand(
             Command::new("next")
@@ -77,19 +365,32 @@ fn cli() -> clap::Command {
         .subcommand(
             Command::new("run")
                 .about("Test a solution against current clash")
-                .arg(arg!(--"build-command" <COMMAND> "command that compiles the solution"))
-                .arg(arg!(--"command" <COMMAND> "command that executes the solution").required(true))
+                .args(solution_command_args())
+                .args(limit_args())
                 .arg(
-                    arg!(--"timeout" <SECONDS> "how many seconds before execution is timed out (0 for no timeout)")
-                        .value_parser(value_parser!(f64))
-                        .default_value("5")
+                    arg!(--"source" <FILE> "solution source file, its length is reported when all testcases pass")
+                        .value_parser(value_parser!(PathBuf))
                 )
+                .arg(arg!(--"save" "save the solution source into the archive if all testcases pass"))
                 .arg(arg!(--"auto-advance" "automatically move on to next clash if all testcases pass"))
                 .arg(arg!(--"ignore-failures" "run all tests despite failures"))
                 .arg(
-                    arg!(--"testcases" <TESTCASE_INDICES> "indices of the testcases to run (separated by commas)")
-                        .value_parser(value_parser!(u64).range(1..99))
-                        .value_delimiter(',')
+                    arg!(--"cg-scoring" "hide the contents of validators and report the score like CodinGame")
+                        .conflicts_with_all(["testcases", "format"])
+                )
+                .arg(
+                    arg!(--"format" <FORMAT> "how to report the results")
+                        .value_parser(value_parser!(ReportFormat))
+                        .default_value("text")
+                )
+                .arg(
+                    arg!(--"watch" <PATH> ... "re-run the build and the testcases whenever PATH changes")
+                        .value_parser(value_parser!(PathBuf))
+                )
+                .arg(
+                    arg!(-'j' --"jobs" <N> "how many testcases to run at the same time")
+                        .value_parser(value_parser!(NonZeroUsize))
+                        .default_value("1")
                 )
                 .arg(
                     arg!(--"show-whitespace" [BOOL] "render ⏎ and • in place of newlines and spaces")
@@ -105,9 +406,145 @@ fn cli() -> clap::Command {
                 .after_help(
                     "If a --build-command is specified, it will be executed once before running any of the testcases. \
                     The --command is required and will be executed once per testcase.\
+                    \nWith --checker COMMAND the output is verified by running COMMAND INPUT_FILE EXPECTED_FILE ACTUAL_FILE \
+                    instead of comparing it to the expected output. Exit code 0 accepts the output and 1 rejects it, \
+                    anything the checker prints is shown in the failure report.\
+                    \nWith --compare MODE the output is compared using one of the built-in comparators: exact, tokens \
+                    (ignores whitespace), case-insensitive, unordered-lines or float[:EPS] (numbers may differ by EPS, \
+                    use float:abs=EPS or float:rel=EPS for only absolute or relative tolerance). \
+                    The default can be changed per clash with `coctus compare`.\
+                    \nWith --format json|junit|tap a machine readable report of every selected testcase is printed \
+                    once the run is over. The exit code is non-zero if any testcase does not pass.\
+                    \nWith --watch PATH... coctus keeps running and re-runs the --build-command and the testcases \
+                    whenever one of the files (or anything inside the directories) changes.\
+                    \nWith --build-sources FILE... the build is skipped when none of the files have changed since the last \
+                    successful build (use --rebuild to build anyway). With --lang the SOURCE is used automatically. \
+                    Builds are killed after --build-timeout seconds.\
+                    \nWith --cg-scoring every testcase is run like when submitting on CodinGame: tests are shown with \
+                    full diffs, but only whether they passed is shown for validators. The score is the percentage of \
+                    validators that pass, rounded down.\
+                    \nWith --jobs N up to N testcases are executed at the same time, results are still reported in order.\
+                    \nWith --lang LANGUAGE SOURCE the build and run commands come from the run profile of LANGUAGE \
+                    instead of --build-command and --command. Built-in profiles exist for the languages supported by \
+                    generate-stub, they can be overridden in run_profiles.toml in the config directory.\
+                    \nWith --source FILE the length of the solution is counted in characters like in shortest mode. \
+                    The shortest passing length for each clash and language (the --lang LANGUAGE, otherwise the file \
+                    extension) is remembered.\
+                    \nWith --save the --source (or the --lang SOURCE) is saved into the solution archive when all \
+                    testcases pass, see `coctus solutions`.\
                     \nIMPORTANT: The commands you provide will be executed without any sandboxing. Only run code you trust!"
                 )
         )
+        .subcommand(
+            Command::new("bench")
+                .about("Measure how fast a solution runs on the testcases of current clash")
+                .args(solution_command_args())
+                .args(limit_args())
+                .arg(
+                    arg!(--"runs" <N> "how many measured runs to do per testcase")
+                        .value_parser(value_parser!(NonZeroUsize))
+                        .default_value("10")
+                )
+                .arg(
+                    arg!(--"warmup" <N> "how many unmeasured runs to do per testcase before measuring")
+                        .value_parser(value_parser!(usize))
+                        .default_value("1")
+                )
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .after_help(
+                    "Runs every selected testcase --warmup + --runs times one after another and reports the \
+                    minimum, median and 95th percentile of the wall clock times of the measured runs. \
+                    The overall timings are computed from the total time of each round over all testcases.\
+                    \nBenchmarking stops at the first testcase that does not pass.\
+                    \nIMPORTANT: The commands you provide will be executed without any sandboxing. Only run code you trust!"
+                )
+        )
+        .subcommand(
+            Command::new("compare")
+                .about("Show or set how the output of solutions is compared for a clash")
+                .arg(
+                    arg!([MODE] "comparator to use by default when running solutions")
+                        .value_parser(Comparator::from_str)
+                )
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .after_help(
+                    "MODE is one of: exact, tokens, case-insensitive, unordered-lines or float[:EPS] \
+                    (see `coctus run --help`). The comparator is remembered for the clash and used by `run` and \
+                    `bench` unless --compare or --checker is given.\
+                    \nWithout MODE the comparator of the current clash is printed."
+                )
+        )
+        .subcommand(
+            Command::new("solutions")
+                .about("Save, list and compare solutions of clashes")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("save")
+                        .about("Save a copy of a solution source into the archive")
+                        .arg(arg!(<SOURCE> "solution source file").value_parser(value_parser!(PathBuf)))
+                        .arg(arg!(--"lang" <LANGUAGE> "language of the solution (default: extension of SOURCE)"))
+                        .arg(
+                            arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                                .value_parser(value_parser!(PublicHandle))
+                        )
+                )
+                .subcommand(
+                    Command::new("list")
+                        .about("List the archived solutions of a clash")
+                        .arg(
+                            arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                                .value_parser(value_parser!(PublicHandle))
+                        )
+                )
+                .subcommand(
+                    Command::new("show")
+                        .about("Print an archived solution")
+                        .arg(arg!(<SOLUTION> "LANGUAGE or LANGUAGE:VERSION").value_parser(SolutionRef::from_str))
+                        .arg(
+                            arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                                .value_parser(value_parser!(PublicHandle))
+                        )
+                )
+                .subcommand(
+                    Command::new("diff")
+                        .about("Show the differences between two archived solutions")
+                        .arg(arg!(<OLD> "LANGUAGE or LANGUAGE:VERSION").value_parser(SolutionRef::from_str))
+                        .arg(arg!(<NEW> "LANGUAGE or LANGUAGE:VERSION").value_parser(SolutionRef::from_str))
+                        .arg(
+                            arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                                .value_parser(value_parser!(PublicHandle))
+                        )
+                )
+                .after_help(
+                    "Solutions are archived per clash and language in the solutions directory of the data \
+                    directory. Every save of a changed source becomes a new version of the language, numbered from 1. \
+                    A SOLUTION is referred to as LANGUAGE (the latest version) or LANGUAGE:VERSION, for example \
+                    `coctus solutions diff python:1 python`.\
+                    \nUse `coctus run --save` to save a solution only when it passes all testcases."
+                )
+        )
+        .subcommand(
+            Command::new("history")
+                .about("Show the results of previous runs")
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .after_help(
+                    "Every `coctus run` is recorded in history.jsonl in the data directory.\
+                    \nWithout PUBLIC_HANDLE every attempted clash is listed with its best result and whether it was \
+                    solved, is still in progress (the current clash) or was abandoned. With PUBLIC_HANDLE every run \
+                    against that clash is listed.\
+                    \nTestcase results are shown with one character per testcase: . for passing, F for wrong output, \
+                    T for timeout, E for other errors and - for testcases that were not run."
+                )
+        )
         .subcommand(
             Command::new("status").about("Show status information")
         )
@@ -168,6 +605,140 @@ fn cli() -> clap::Command {
                     \n  $ coctus generate-stub bash > sol.sh"
             )
         )
+        .subcommand(
+            Command::new("gen-input")
+                .about("Generate random inputs with the shape described by the stub generator of a clash")
+                .arg(
+                    arg!(--"range" <RANGE> "range of the values of a variable as VARIABLE=MIN..MAX")
+                        .value_parser(parse_variable_range)
+                        .action(clap::ArgAction::Append)
+                )
+                .arg(arg!(--"constraints" "use the bounds found in the constraints of the clash as ranges"))
+                .arg(arg!(--"seed" <SEED> "seed for the random number generator").value_parser(value_parser!(u64)))
+                .arg(
+                    arg!(-'n' --"count" <N> "how many inputs to generate")
+                        .value_parser(value_parser!(NonZeroUsize))
+                        .default_value("1")
+                )
+                .arg(
+                    arg!(--"output-dir" <DIR> "write the inputs to DIR/input_N.txt instead of STDOUT")
+                        .value_parser(value_parser!(PathBuf))
+                )
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .after_help(
+                    "Every variable read by the stub gets a random value. By default integers are between 0 and 100 \
+                    (loop counts and lengths between 1 and 10), longs between 0 and 10^9, floats between 0 and 100, \
+                    words are 1 to 10 and strings 1 to 20 characters long (but never longer than their maximum \
+                    length). Use --range to change them, for words and strings the range is their length:\
+                    \n  $ coctus gen-input --range N=1..1000 --range name=3..8\
+                    \nWith --constraints the bounds found in the constraints of the clash (see `coctus show \
+                    --constraints`) are used instead of the defaults, --range still overrides them.\
+                    \nThe seed is printed to STDERR when --seed is not given so interesting inputs can be \
+                    generated again. Several inputs printed to STDOUT are separated by empty lines."
+                )
+        )
+        .subcommand(
+            Command::new("diff-test")
+                .about("Compare a solution to a reference solution on random inputs")
+                .arg(arg!(--"reference" <COMMAND> "command that executes a solution known to be correct").required(true))
+                .arg(arg!(--"candidate" <COMMAND> "command that executes the solution to test").required(true))
+                .arg(
+                    arg!(--"runs" <N> "how many random inputs to try")
+                        .value_parser(value_parser!(NonZeroUsize))
+                        .default_value("100")
+                )
+                .arg(
+                    arg!(--"range" <RANGE> "range of the values of a variable as VARIABLE=MIN..MAX")
+                        .value_parser(parse_variable_range)
+                        .action(clap::ArgAction::Append)
+                )
+                .arg(arg!(--"constraints" "use the bounds found in the constraints of the clash as ranges"))
+                .arg(arg!(--"seed" <SEED> "seed for the random number generator").value_parser(value_parser!(u64)))
+                .arg(
+                    arg!(--"compare" <MODE> "how to compare the outputs (default: the comparator of the clash)")
+                        .value_parser(Comparator::from_str)
+                )
+                .args(limit_args())
+                .arg(
+                    arg!(--"max-shrinks" <N> "how many smaller inputs to try at most when shrinking")
+                        .value_parser(value_parser!(usize))
+                        .default_value("300")
+                )
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .after_help(
+                    "Random inputs are generated from the stub generator of the clash like with `coctus gen-input` \
+                    and fed to both commands. The first input the candidate gets wrong (a different output, a crash \
+                    or a timeout) is shrunk to a minimal counterexample by removing loop iterations, making numbers \
+                    closer to 0 and making words shorter for as long as the outputs still differ. Inputs that the \
+                    reference fails on are skipped. Use --constraints and --range to limit the values like with \
+                    `coctus gen-input`.\
+                    \nIMPORTANT: The commands you provide will be executed without any sandboxing. Only run code you trust!"
+                )
+        )
+        .subcommand(
+            Command::new("tests")
+                .about("Add, edit and remove local testcases of a clash")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("add")
+                        .about("Add a local testcase")
+                        .args(local_testcase_args())
+                        .arg(
+                            arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                                .value_parser(value_parser!(PublicHandle))
+                        )
+                )
+                .subcommand(
+                    Command::new("edit")
+                        .about("Change a local testcase")
+                        .arg(arg!(<INDEX> "index of the testcase").value_parser(value_parser!(usize)))
+                        .args(local_testcase_args())
+                        .arg(
+                            arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                                .value_parser(value_parser!(PublicHandle))
+                        )
+                )
+                .subcommand(
+                    Command::new("remove")
+                        .about("Remove a local testcase")
+                        .arg(arg!(<INDEX> "index of the testcase").value_parser(value_parser!(usize)))
+                        .arg(
+                            arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                                .value_parser(value_parser!(PublicHandle))
+                        )
+                )
+                .after_help(
+                    "Local testcases are stored per clash in the local_testcases directory of the data directory. \
+                    They are numbered after the testcases of the clash, `coctus run` and `coctus showtests` \
+                    include them with their titles marked as [local]. They are not validators, so they don't \
+                    count towards the score of `coctus run --cg-scoring`.\
+                    \nWithout --input and --output (or --edit) the testcase is opened in $VISUAL or $EDITOR:\
+                    \n  $ coctus tests add --title \"Empty list\" --input 0 --output NONE\
+                    \n  $ coctus tests edit 9"
+                )
+        )
+        .subcommand(
+            Command::new("check-tests")
+                .about("Check that the testcase inputs of a clash match its stub generator and constraints")
+                .arg(arg!(--"no-constraints" "only check the inputs against the stub generator"))
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .after_help(
+                    "Every testcase and validator input is read the way the stub generator describes. Lines with \
+                    the wrong number of values, values of the wrong type, words and strings longer than their \
+                    maximum length and missing or extra lines are reported, as well as values outside the bounds \
+                    found in the constraints (see `coctus show --constraints`). The constraints are free text, so \
+                    use --no-constraints if the bounds found in them are wrong."
+                )
+        )
         .subcommand(
             Command::new("generate-shell-completion")
                 .about("Generate shell completion")
@@ -187,15 +758,33 @@ fn cli() -> clap::Command {
 struct App {
     clash_dir: PathBuf,
     current_clash_file: PathBuf,
+    golf_records_file: PathBuf,
+    clash_settings_file: PathBuf,
     stub_templates_dir: PathBuf,
+    run_profiles_file: PathBuf,
+    build_cache_file: PathBuf,
+    history_file: PathBuf,
+    solutions_dir: PathBuf,
+    local_testcases_dir: PathBuf,
+    clash_index_file: PathBuf,
+    builds_dir: PathBuf,
 }
 
 impl App {
-    fn new(data_dir: &std::path::Path, config_dir: &std::path::Path) -> App {
+    fn new(data_dir: &std::path::Path, config_dir: &std::path::Path, cache_dir: &std::path::Path) -> App {
         App {
             clash_dir: data_dir.join("clashes"),
             current_clash_file: data_dir.join("current"),
+            golf_records_file: data_dir.join("golf_records.json"),
+            clash_settings_file: data_dir.join("clash_settings.json"),
             stub_templates_dir: config_dir.join("stub_templates"),
+            run_profiles_file: config_dir.join("run_profiles.toml"),
+            build_cache_file: data_dir.join("build_cache.json"),
+            history_file: data_dir.join("history.jsonl"),
+            solutions_dir: data_dir.join("solutions"),
+            local_testcases_dir: data_dir.join("local_testcases"),
+            clash_index_file: data_dir.join("clash_index.json"),
+            builds_dir: cache_dir.join("builds"),
         }
     }
 
@@ -206,40 +795,34 @@ impl App {
         PublicHandle::from_str(&content)
     }
 
-    fn clashes(&self) -> Result<std::fs::ReadDir> {
-        std::fs::read_dir(&self.clash_dir).with_context(|| "No clashes stored")
+    /// Index of the stored clashes, updated with any changes to the clash
+    /// files since it was last used.
+    fn clash_index(&self) -> Result<ClashIndex> {
+        let mut index = ClashIndex::load(&self.clash_index_file)?;
+        if index.sync(&self.clash_dir)? {
+            index.save(&self.clash_index_file)?;
+        }
+        Ok(index)
     }
 
     fn random_handle(&self) -> Result<PublicHandle> {
-        let mut rng = rand::thread_rng();
-        if let Ok(entry) = self.clashes()?.choose(&mut rng).expect("No clashes to choose from!") {
-            let filename =
-                entry.file_name().into_string().expect("unable to convert OsString to String (?!?)");
-            PublicHandle::from_str(match filename.strip_suffix(".json") {
-                Some(handle) => handle,
-                None => &filename,
-            })
-        } else {
-            Err(anyhow!("Unable to randomize next clash"))
-        }
+        self.random_handle_with_modes(false, false, false)
     }
 
     fn random_handle_with_modes(&self, fastest: bool, shortest: bool, reverse: bool) -> Result<PublicHandle> {
-        let max_attemps = 100;
-        for _ in 0..max_attemps {
-            let handle = self.random_handle()?;
-            let clash = self.read_clash(&handle)?;
-            if (!reverse || clash.is_reverse())
-                && (!fastest || clash.is_fastest())
-                && (!shortest || clash.is_shortest())
-            {
-                return Ok(handle)
-            }
+        let index = self.clash_index()?;
+        if index.len() == 0 {
+            return Err(anyhow!("No clashes stored, use `coctus fetch` to download some"))
+        }
+        let candidates: Vec<&str> = index
+            .entries()
+            .filter(|entry| entry.has_modes(fastest, shortest, reverse))
+            .map(|entry| entry.handle.as_str())
+            .collect();
+        match candidates.choose(&mut rand::thread_rng()) {
+            Some(handle) => PublicHandle::from_str(handle),
+            None => Err(anyhow!("None of the {} stored clashes has all the required modes", index.len())),
         }
-        Err(anyhow!(format!(
-            "Failed to find a clash with the required modes after {} attempts.",
-            max_attemps
-        )))
     }
 
     fn read_clash(&self, handle: &PublicHandle) -> Result<Clash> {
@@ -261,6 +844,11 @@ impl App {
         let show_whitespace = *args.get_one::<bool>("show-whitespace").unwrap_or(&false);
         let ostyle = OutputStyle::from_env(show_whitespace);
 
+        if args.get_flag("constraints") {
+            print_constraints_table(&clash.variable_constraints(), &ostyle);
+            return Ok(())
+        }
+
         // --reverse flag
         if args.get_flag("reverse") {
             if clash.is_reverse() {
@@ -302,6 +890,26 @@ impl App {
         Ok(())
     }
 
+    fn compare(&self, args: &ArgMatches) -> Result<()> {
+        let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+            Some(h) => h.to_owned(),
+            None => self.current_handle()?,
+        };
+        let mut settings = ClashSettings::load(&self.clash_settings_file)?;
+        match args.get_one::<Comparator>("MODE") {
+            Some(comparator) => {
+                settings.set_comparator(&handle, *comparator);
+                settings.save(&self.clash_settings_file)?;
+                println!("Comparing output of clash {handle} using: {comparator}");
+            }
+            None => {
+                let comparator = settings.comparator(&handle)?.unwrap_or_default();
+                println!("{comparator}");
+            }
+        }
+        Ok(())
+    }
+
     fn status(&self, _args: &ArgMatches) -> Result<()> {
         println!("Current clash file: {}", self.current_clash_file.display());
         match self.current_handle() {
@@ -309,87 +917,560 @@ impl App {
             Err(_) => println!("Current clash: -"),
         }
         println!("Clash dir: {}", self.clash_dir.display());
-        let num_clashes = match self.clashes() {
-            Ok(clashes) => clashes.count(),
-            Err(_) => 0,
-        };
-        println!("Number of clashes: {}", num_clashes);
+        let index = self.clash_index()?;
+        println!("Number of clashes: {}", index.len());
+        if index.len() > 0 {
+            let count = |filter: &dyn Fn(&internal::IndexEntry) -> bool| {
+                index.entries().filter(|entry| filter(entry)).count()
+            };
+            println!(
+                "  Clash of Code: {}, classic puzzles: {}",
+                count(&|entry| entry.puzzle_type == PuzzleType::Clash),
+                count(&|entry| entry.puzzle_type == PuzzleType::ClassicInOut)
+            );
+            println!(
+                "  Fastest: {}, shortest: {}, reverse: {}",
+                count(&|entry| entry.fastest),
+                count(&|entry| entry.shortest),
+                count(&|entry| entry.reverse)
+            );
+            println!("  With a stub generator: {}", count(&|entry| entry.has_stub_generator));
+            if let Some(latest) = index.entries().max_by_key(|entry| entry.fetched_at) {
+                println!(
+                    "Last fetched: {} {} ({})",
+                    latest.handle,
+                    latest.title,
+                    internal::format_timestamp(latest.fetched_at)
+                );
+            }
+        }
         Ok(())
     }
 
     fn run(&self, args: &ArgMatches) -> Result<()> {
+        let Some(watched_paths) = args.get_many::<PathBuf>("watch") else {
+            return self.run_once(args)
+        };
+
+        let mut watcher = FileWatcher::new(watched_paths.cloned().collect());
+        loop {
+            watcher.run_then_wait(|| {
+                // Clear the screen and move the cursor to the top left corner
+                print!("\x1B[2J\x1B[H");
+                if let Err(err) = self.run_once(args) {
+                    eprintln!("Error: {err:?}");
+                }
+                eprintln!("Watching for changes... (press Ctrl-C to stop)");
+            });
+        }
+    }
+
+    fn run_once(&self, args: &ArgMatches) -> Result<()> {
         let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
             Some(h) => h.to_owned(),
             None => self.current_handle()?,
         };
 
-        if let Some(mut build_command) = command_from_argument(args.get_one::<String>("build-command"))? {
-            let build = build_command.output()?;
-
-            if !build.status.success() {
-                if !build.stderr.is_empty() {
-                    println!("Build command STDERR:\n{}", String::from_utf8(build.stderr)?);
-                }
-                if !build.stdout.is_empty() {
-                    println!("Build command STDOUT:\n{}", String::from_utf8(build.stdout)?);
-                }
-                return Err(anyhow!("Build failed"))
-            }
+        let mut commands = self.solution_commands(args)?;
+        if let Err(err) = self.build_solution(&mut commands, args) {
+            self.record_attempt(&handle, args, &commands, Outcome::BuildFailed, &Report::new(&handle))?;
+            return Err(err)
         }
+        let run_command = &mut commands.run;
 
-        let mut run_command = command_from_argument(args.get_one::<String>("command"))?
-            .expect("clap should ensure `run` can't be executed without a --command");
+        let limits = limits_from_args(args)?;
+        let clash_comparator = ClashSettings::load(&self.clash_settings_file)?.comparator(&handle)?;
+        let verifier = verifier_from_args(args, clash_comparator)?;
 
-        let timeout = match *args.get_one::<f64>("timeout").unwrap_or(&5.0) {
-            secs if secs.is_nan() => return Err(anyhow!("Timeout can't be NaN")),
-            secs if secs < 0.0 => return Err(anyhow!("Timeout can't be negative (use 0 for no timeout)")),
-            secs if secs == 0.0 => std::time::Duration::MAX,
-            secs => std::time::Duration::from_micros((secs * 1e6) as u64),
-        };
+        let clash = self.read_clash(&handle)?;
+        let all_testcases = self.all_testcases(&handle, &clash)?;
+        let is_local = |testcase: &Testcase| testcase.index > clash.testcases().len();
 
-        let all_testcases = self.read_clash(&handle)?.testcases().to_owned();
+        let testcases = selected_testcases(&all_testcases, args)?;
+        let selected = testcases.clone();
 
-        let testcases: Vec<&Testcase> = if let Some(testcase_indices) = args.get_many::<u64>("testcases") {
-            testcase_indices.map(|idx| &all_testcases[(idx - 1) as usize]).collect()
+        let num_tests = testcases.len();
+        let cg_scoring = args.get_flag("cg-scoring");
+        let jobs = *args.get_one::<NonZeroUsize>("jobs").expect("--jobs has a default value");
+        let suite_run: Box<dyn Iterator<Item = _>> = if jobs.get() > 1 {
+            Box::new(solution::parallel_run(testcases, run_command, &limits, &verifier, jobs).into_iter())
         } else {
-            all_testcases.iter().collect()
+            Box::new(solution::lazy_run(testcases, run_command, &limits, &verifier).into_iter())
         };
 
-        let num_tests = testcases.len();
-        let suite_run = solution::lazy_run(testcases, &mut run_command, &timeout);
-
-        let ignore_failures = args.get_flag("ignore-failures");
+        // CodinGame runs every validator no matter how many fail
+        let ignore_failures = args.get_flag("ignore-failures") || cg_scoring;
         let show_whitespace = *args.get_one::<bool>("show-whitespace").unwrap_or(&false);
         let ostyle = OutputStyle::from_env(show_whitespace);
+        let format = *args.get_one::<ReportFormat>("format").expect("--format has a default value");
+        // Keep STDOUT clean for machine readable reports
+        let print_note = |note: &str| match format {
+            ReportFormat::Text => println!("{note}"),
+            _ => eprintln!("{note}"),
+        };
 
         let mut num_passed = 0;
+        let mut num_validators_passed = 0;
+        let mut num_local_passed = 0;
+        let mut total_usage = solution::ResourceUsage::default();
+        let mut report = Report::new(&handle);
 
         for (testcase, test_result) in suite_run {
-            ostyle.print_result(testcase, &test_result);
+            if cg_scoring && testcase.is_validator {
+                ostyle.print_hidden_result(testcase, &test_result);
+                num_validators_passed += usize::from(test_result.is_success());
+            } else if format == ReportFormat::Text {
+                ostyle.print_result(testcase, &test_result, &verifier.comparator());
+            }
+            if let Some(usage) = test_result.usage() {
+                total_usage.accumulate(usage);
+            }
+            report.add(testcase, &test_result);
 
             if test_result.is_success() {
                 num_passed += 1;
+                num_local_passed += usize::from(is_local(testcase));
             } else if !ignore_failures {
                 break
             }
         }
-        println!("{num_passed}/{num_tests} tests passed");
+        for testcase in &selected[report.len()..] {
+            report.skip(testcase);
+        }
+
+        let num_validators = selected.iter().filter(|testcase| testcase.is_validator).count();
+        let score = internal::codingame_score(num_validators_passed, num_validators);
+        match format {
+            ReportFormat::Text if cg_scoring => {
+                // Local testcases are not part of the score
+                let num_local = selected.iter().filter(|testcase| is_local(testcase)).count();
+                let num_visible = num_tests - num_validators - num_local;
+                let num_visible_passed = num_passed - num_validators_passed - num_local_passed;
+                println!(
+                    "{num_visible_passed}/{num_visible} tests passed {}",
+                    ostyle.styled_usage(&total_usage)
+                );
+                println!("{num_validators_passed}/{num_validators} validators passed");
+                if num_local > 0 {
+                    println!("{num_local_passed}/{num_local} local tests passed (not scored)");
+                }
+                match score {
+                    Some(score) => println!("{}", ostyle.title.paint(format!("Score: {score}%"))),
+                    None => println!("Score: - (the clash has no validators)"),
+                }
+            }
+            ReportFormat::Text => {
+                println!("{num_passed}/{num_tests} tests passed {}", ostyle.styled_usage(&total_usage))
+            }
+            ReportFormat::Json => println!("{}", report.to_json()?),
+            ReportFormat::Junit => println!("{}", report.to_junit()),
+            ReportFormat::Tap => println!("{}", report.to_tap()),
+        }
+
+        let passed = match score {
+            Some(score) if cg_scoring => score == 100,
+            _ => num_passed == num_tests,
+        };
+        let outcome = if passed { Outcome::Passed } else { Outcome::Failed };
+        self.record_attempt(&handle, args, &commands, outcome, &report)?;
+        if !passed {
+            if let (true, Some(score)) = (cg_scoring, score) {
+                return Err(anyhow!(
+                    "Scored {score}%, {} validators did not pass",
+                    num_validators - num_validators_passed
+                ))
+            }
+            return Err(anyhow!("{} of {num_tests} testcases did not pass", num_tests - num_passed))
+        }
+
+        if let Some(source_file) = solution_source(args) {
+            let language = solution_language(args).unwrap_or_else(|| String::from("unknown"));
+            print_note(&self.report_golf_length(&handle, &source_file, &language)?);
+        }
+
+        if args.get_flag("save") {
+            let Some(source_file) = solution_source(args) else {
+                return Err(anyhow!("--save requires --source or --lang"))
+            };
+            let language = solution_language(args).context("Unable to tell the language of the solution")?;
+            print_note(&self.save_solution(&handle, &language, &source_file)?);
+        }
 
         // Move on to next clash if --auto-advance is set
-        if num_passed == num_tests && args.get_flag("auto-advance") {
+        if args.get_flag("auto-advance") {
             let next_handle = self.random_handle()?;
             std::fs::write(&self.current_clash_file, next_handle.to_string())?;
-            println!("Moving on to next clash...");
+            print_note("Moving on to next clash...");
         }
 
         Ok(())
     }
 
+    fn solution_commands(&self, args: &ArgMatches) -> Result<SolutionCommands> {
+        if let Some(mut values) = args.get_many::<String>("lang") {
+            let (Some(language), Some(source)) = (values.next(), values.next()) else {
+                unreachable!("clap should ensure --lang has two values")
+            };
+            let profiles = RunProfiles::load(&self.run_profiles_file)?;
+            return profiles.get(language)?.commands(std::path::Path::new(source), &self.builds_dir)
+        }
+
+        let build = command_from_argument(args.get_one::<String>("build-command"))?;
+        let run = command_from_argument(args.get_one::<String>("command"))?
+            .expect("clap should ensure a solution can't be executed without --command or --lang");
+        let sources = args.get_many::<PathBuf>("build-sources").unwrap_or_default().cloned().collect();
+        Ok(SolutionCommands::new(build, run, sources))
+    }
+
+    fn record_attempt(
+        &self,
+        handle: &PublicHandle,
+        args: &ArgMatches,
+        commands: &SolutionCommands,
+        outcome: Outcome,
+        report: &Report,
+    ) -> Result<()> {
+        let lang: Option<Vec<&String>> = args.get_many::<String>("lang").map(Iterator::collect);
+        let command = match (&lang, args.get_one::<String>("command")) {
+            (Some(values), _) => format!("--lang {} {}", values[0], values[1]),
+            (None, Some(command)) => command.clone(),
+            (None, None) => unreachable!("clap should ensure there is --command or --lang"),
+        };
+        let mut solution_files = commands.sources.clone();
+        solution_files.extend(args.get_one::<PathBuf>("source").cloned());
+        // The files may have been removed in the meantime, that's not worth failing the
+        // run over
+        let solution_hash = match solution_files.is_empty() {
+            true => None,
+            false => internal::hash_files(&solution_files).ok(),
+        };
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |since_epoch| since_epoch.as_secs());
+
+        let attempt = Attempt {
+            timestamp,
+            handle: handle.to_string(),
+            language: solution_language(args),
+            command,
+            solution_hash,
+            outcome,
+            testcases: report.testcase_outcomes(),
+        };
+        History::append(&self.history_file, &attempt)
+    }
+
+    /// The testcases of a clash followed by its local testcases.
+    fn all_testcases(&self, handle: &PublicHandle, clash: &Clash) -> Result<Vec<Testcase>> {
+        let locals = LocalTestcases::load(&self.local_testcases_dir, handle)?;
+        Ok(locals.merged_with(clash.testcases()))
+    }
+
+    fn tests(&self, args: &ArgMatches) -> Result<()> {
+        let (subcommand, args) = args.subcommand().expect("clap should ensure a subcommand is given");
+        let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+            Some(h) => h.to_owned(),
+            None => self.current_handle()?,
+        };
+        let num_clash_testcases = self.read_clash(&handle)?.testcases().len();
+        let mut locals = LocalTestcases::load(&self.local_testcases_dir, &handle)?;
+
+        let (title, test_in, test_out) = match subcommand {
+            "add" | "edit" => (
+                args.get_one::<String>("title"),
+                testcase_text(args, "input")?,
+                testcase_text(args, "output")?,
+            ),
+            _ => (None, None, None),
+        };
+        match subcommand {
+            "add" => {
+                let index = num_clash_testcases + locals.len() + 1;
+                let mut testcase = LocalTestcase {
+                    title: title.cloned().unwrap_or_else(|| format!("Local test {}", locals.len() + 1)),
+                    test_in: test_in.clone().unwrap_or_default(),
+                    test_out: test_out.clone().unwrap_or_default(),
+                };
+                if test_in.is_none() || test_out.is_none() || args.get_flag("edit") {
+                    testcase = LocalTestcase::from_editable(&edit_in_editor(&testcase.to_editable())?)?;
+                }
+                println!("Added testcase #{index} {}", testcase.title);
+                locals.push(testcase);
+            }
+            "edit" => {
+                let index = *args.get_one::<usize>("INDEX").expect("INDEX is required");
+                let testcase = locals.get_mut(index, num_clash_testcases)?;
+                let given = title.is_some() || test_in.is_some() || test_out.is_some();
+                if let Some(title) = title {
+                    testcase.title = title.clone();
+                }
+                if let Some(test_in) = test_in {
+                    testcase.test_in = test_in;
+                }
+                if let Some(test_out) = test_out {
+                    testcase.test_out = test_out;
+                }
+                if !given || args.get_flag("edit") {
+                    *testcase = LocalTestcase::from_editable(&edit_in_editor(&testcase.to_editable())?)?;
+                }
+                println!("Changed testcase #{index} {}", testcase.title);
+            }
+            "remove" => {
+                let index = *args.get_one::<usize>("INDEX").expect("INDEX is required");
+                let removed = locals.remove(index, num_clash_testcases)?;
+                println!("Removed testcase #{index} {}", removed.title);
+            }
+            _ => unreachable!("clap should only allow known subcommands"),
+        }
+        locals.save()
+    }
+
+    fn save_solution(
+        &self,
+        handle: &PublicHandle,
+        language: &str,
+        source: &std::path::Path,
+    ) -> Result<String> {
+        let archive = SolutionArchive::new(&self.solutions_dir);
+        let message = match archive.save(handle, language, source)? {
+            (archived, true) => format!("Saved solution as {archived}"),
+            (archived, false) => format!("Solution is already saved as {archived}"),
+        };
+        Ok(message)
+    }
+
+    fn solutions(&self, args: &ArgMatches) -> Result<()> {
+        let (subcommand, args) = args.subcommand().expect("clap should ensure a subcommand is given");
+        let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+            Some(h) => h.to_owned(),
+            None => self.current_handle()?,
+        };
+        let archive = SolutionArchive::new(&self.solutions_dir);
+
+        match subcommand {
+            "save" => {
+                let source = args.get_one::<PathBuf>("SOURCE").expect("SOURCE is required");
+                let language = match args.get_one::<String>("lang") {
+                    Some(language) => language.clone(),
+                    None => source
+                        .extension()
+                        .map(|ext| ext.to_string_lossy().into_owned())
+                        .context("Unable to tell the language of the solution, use --lang")?,
+                };
+                println!("{}", self.save_solution(&handle, &language, source)?);
+            }
+            "list" => {
+                let solutions = archive.list(&handle)?;
+                if solutions.is_empty() {
+                    println!("No saved solutions for clash {handle}");
+                }
+                for archived in solutions {
+                    let source = std::fs::read_to_string(&archived.path)
+                        .with_context(|| format!("Unable to read {:?}", archived.path))?;
+                    let saved_at = std::fs::metadata(&archived.path)
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                        .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+                        .map_or(String::from("-"), |since_epoch| {
+                            internal::format_timestamp(since_epoch.as_secs())
+                        });
+                    println!(
+                        "{:<12}  {:>5} chars  {saved_at}  {}",
+                        archived.to_string(),
+                        solution::code_length(&source),
+                        archived.path.display()
+                    );
+                }
+            }
+            "show" => {
+                let solution = args.get_one::<SolutionRef>("SOLUTION").expect("SOLUTION is required");
+                let archived = archive.get(&handle, solution)?;
+                let source = std::fs::read_to_string(&archived.path)
+                    .with_context(|| format!("Unable to read {:?}", archived.path))?;
+                print!("{source}");
+            }
+            "diff" => {
+                let mut sources = Vec::new();
+                for id in ["OLD", "NEW"] {
+                    let solution = args.get_one::<SolutionRef>(id).expect("OLD and NEW are required");
+                    let archived = archive.get(&handle, solution)?;
+                    let source = std::fs::read_to_string(&archived.path)
+                        .with_context(|| format!("Unable to read {:?}", archived.path))?;
+                    sources.push(source);
+                }
+                OutputStyle::from_env(false).print_source_diff(&sources[0], &sources[1]);
+            }
+            _ => unreachable!("clap should only allow known subcommands"),
+        }
+        Ok(())
+    }
+
+    fn history(&self, args: &ArgMatches) -> Result<()> {
+        let attempts = History::load(&self.history_file)?;
+        let ostyle = OutputStyle::from_env(false);
+
+        if let Some(handle) = args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+            let handle = handle.to_string();
+            let attempts: Vec<&Attempt> =
+                attempts.iter().filter(|attempt| attempt.handle == handle).collect();
+            if attempts.is_empty() {
+                println!("No runs recorded for clash {handle}");
+            }
+            for attempt in attempts {
+                let result = match attempt.outcome {
+                    Outcome::BuildFailed => ostyle.failure.paint("build failed").to_string(),
+                    Outcome::Passed => ostyle.success.paint(attempt.result_symbols()).to_string(),
+                    Outcome::Failed => ostyle.failure.paint(attempt.result_symbols()).to_string(),
+                };
+                let hash = attempt.solution_hash.as_deref().map_or("-", |hash| &hash[..hash.len().min(8)]);
+                println!(
+                    "{}  {:>5}  {:<8}  {result}  {}",
+                    internal::format_timestamp(attempt.timestamp),
+                    format!("{}/{}", attempt.num_passed(), attempt.testcases.len()),
+                    hash,
+                    attempt.language.as_deref().unwrap_or(&attempt.command),
+                );
+            }
+            return Ok(())
+        }
+
+        let current = self.current_handle().ok();
+        let clashes = History::by_clash(&attempts, current.as_ref());
+        if clashes.is_empty() {
+            println!("No runs recorded yet");
+        }
+        for clash in clashes {
+            let best = clash.best().map_or(String::from("-"), |attempt| {
+                format!("{}/{}", attempt.num_passed(), attempt.testcases.len())
+            });
+            let status = match clash.status {
+                internal::ClashStatus::Solved => ostyle.success.paint(clash.status.to_string()),
+                _ => ostyle.failure.paint(clash.status.to_string()),
+            };
+            println!(
+                "{}  {}  {:>3} runs  best {best:>5}  {status}",
+                internal::format_timestamp(clash.last_timestamp()),
+                clash.handle,
+                clash.attempts.len(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Run the build command (if any), unless the declared sources are
+    /// unchanged since the last successful build.
+    fn build_solution(&self, commands: &mut SolutionCommands, args: &ArgMatches) -> Result<()> {
+        let has_build_artifacts = commands.has_build_artifacts();
+        let Some(build_command) = commands.build.as_mut() else {
+            return Ok(())
+        };
+        // Float literal patterns are a future-compat warning on the MSRV
+        #[allow(clippy::redundant_guards)]
+        let timeout =
+            match *args.get_one::<f64>("build-timeout").expect("--build-timeout has a default value") {
+                secs if secs.is_nan() => return Err(anyhow!("Build timeout can't be NaN")),
+                secs if secs < 0.0 => {
+                    return Err(anyhow!("Build timeout can't be negative (use 0 for no timeout)"))
+                }
+                secs if secs == 0.0 => std::time::Duration::MAX,
+                secs => std::time::Duration::from_micros((secs * 1e6) as u64),
+            };
+
+        let sources_hash = match commands.sources.as_slice() {
+            [] => None,
+            sources => Some(internal::hash_files(sources)?),
+        };
+        let mut cache = BuildCache::load(&self.build_cache_file)?;
+        if let Some(hash) = &sources_hash {
+            if !args.get_flag("rebuild") && has_build_artifacts && cache.is_fresh(build_command, hash) {
+                eprintln!("Sources unchanged since the last build, skipping build");
+                return Ok(())
+            }
+        }
+
+        // A failed build may have left the previous artifacts in a broken state
+        cache.invalidate(build_command);
+        cache.save(&self.build_cache_file)?;
+        let elapsed = internal::run_build(build_command, timeout)?;
+        eprintln!("Build finished in {:.2}s", elapsed.as_secs_f64());
+        if let Some(hash) = sources_hash {
+            cache.record(build_command, hash);
+            cache.save(&self.build_cache_file)?;
+        }
+        Ok(())
+    }
+
+    // Records are kept per language, under the same name as in the archive
+    fn report_golf_length(
+        &self,
+        handle: &PublicHandle,
+        source_file: &std::path::Path,
+        language: &str,
+    ) -> Result<String> {
+        let source = std::fs::read_to_string(source_file)
+            .with_context(|| format!("Unable to read solution source {:?}", source_file))?;
+        let length = solution::code_length(&source);
+
+        let mut records = GolfRecords::load(&self.golf_records_file)?;
+        let message = match records.record(handle, language, length) {
+            None => format!("Solution length: {length} characters (first {language} record)"),
+            Some(best) if length < best => {
+                format!("Solution length: {length} characters (new {language} record, previous best {best})")
+            }
+            Some(best) => format!("Solution length: {length} characters ({language} record is {best})"),
+        };
+        records.save(&self.golf_records_file)?;
+        Ok(message)
+    }
+
+    fn bench(&self, args: &ArgMatches) -> Result<()> {
+        let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+            Some(h) => h.to_owned(),
+            None => self.current_handle()?,
+        };
+
+        let mut commands = self.solution_commands(args)?;
+        self.build_solution(&mut commands, args)?;
+        let run_command = &mut commands.run;
+        let limits = limits_from_args(args)?;
+        let clash_comparator = ClashSettings::load(&self.clash_settings_file)?.comparator(&handle)?;
+        let verifier = verifier_from_args(args, clash_comparator)?;
+        let runs = args.get_one::<NonZeroUsize>("runs").expect("--runs has a default value").get();
+        let warmup = *args.get_one::<usize>("warmup").expect("--warmup has a default value");
+
+        let all_testcases = self.read_clash(&handle)?.testcases().to_owned();
+        let testcases = selected_testcases(&all_testcases, args)?;
+
+        let ostyle = OutputStyle::from_env(false);
+        let mut round_totals = vec![std::time::Duration::ZERO; runs];
+
+        for testcase in testcases {
+            match solution::benchmark_testcase(testcase, run_command, &limits, &verifier, warmup, runs) {
+                Ok(timings) => {
+                    for (total, timing) in round_totals.iter_mut().zip(&timings) {
+                        *total += *timing;
+                    }
+                    let stats = TimingStats::from_timings(&timings).expect("--runs should be at least 1");
+                    ostyle.print_timing_stats(&ostyle.styled_testcase_title(testcase), &stats);
+                }
+                Err(test_result) => {
+                    ostyle.print_result(testcase, &test_result, &verifier.comparator());
+                    return Err(anyhow!("Solution must pass every testcase to be benchmarked"))
+                }
+            }
+        }
+
+        let overall = TimingStats::from_timings(&round_totals).expect("--runs should be at least 1");
+        ostyle.print_timing_stats(&ostyle.title.paint("Overall").to_string(), &overall);
+        Ok(())
+    }
+
     fn fetch(&self, args: &ArgMatches) -> Result<()> {
         std::fs::create_dir_all(&self.clash_dir)?;
         let handles = args
             .get_many::<PublicHandle>("PUBLIC_HANDLE")
             .with_context(|| "Should have many handles")?;
+        let mut index = self.clash_index()?;
         for handle in handles {
             let req = ureq::post("https://www.codingame.com/services/Contribution/findContribution")
                 .set("Content-Type", "application/json");
@@ -402,14 +1483,23 @@ impl App {
             let clash_file_path = self.clash_dir.join(format!("{}.json", handle));
             std::fs::write(&clash_file_path, &content)?;
             println!("Saved clash {} as {}", &handle, &clash_file_path.display());
+            match serde_json::from_str::<Clash>(&content) {
+                Ok(clash) => {
+                    let fetched_at = std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map_or(0, |since_epoch| since_epoch.as_secs());
+                    index.update(handle, &clash, &clash_file_path, fetched_at)?;
+                }
+                Err(err) => eprintln!("Unable to add clash {handle} to the clash index: {err}"),
+            }
         }
-        Ok(())
+        index.save(&self.clash_index_file)
     }
 
     fn showtests(&self, args: &ArgMatches) -> Result<()> {
         let handle = self.current_handle()?;
         let clash = self.read_clash(&handle)?;
-        let all_testcases = clash.testcases();
+        let all_testcases = self.all_testcases(&handle, &clash)?;
 
         let show_whitespace = *args.get_one::<bool>("show-whitespace").unwrap_or(&false);
         let ostyle = OutputStyle::from_env(show_whitespace);
@@ -452,6 +1542,154 @@ impl App {
         Ok(())
     }
 
+    /// Input generator for the stub generator of a clash with the ranges
+    /// from the constraints (with `--constraints`) and `--range`.
+    fn input_generator(&self, handle: &PublicHandle, args: &ArgMatches) -> Result<stub::InputGenerator> {
+        let clash = self.read_clash(handle)?;
+        let stub_generator = clash.stub_generator().context("Clash provides no input stub generator")?;
+        let mut generator = stub::InputGenerator::new(stub_generator)?;
+        if args.get_flag("constraints") {
+            generator.apply_constraints(&clash.variable_constraints());
+        }
+        for (variable, range) in args.get_many::<(String, stub::ValueRange)>("range").unwrap_or_default() {
+            generator.set_range(variable, *range)?;
+        }
+        Ok(generator)
+    }
+
+    fn gen_input(&self, args: &ArgMatches) -> Result<()> {
+        let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+            Some(h) => h.to_owned(),
+            None => self.current_handle()?,
+        };
+        let generator = self.input_generator(&handle, args)?;
+        let mut rng = seeded_rng(args);
+        let count = args.get_one::<NonZeroUsize>("count").expect("--count has a default value").get();
+        let output_dir = args.get_one::<PathBuf>("output-dir");
+        if let Some(dir) = output_dir {
+            std::fs::create_dir_all(dir).with_context(|| format!("Unable to create directory {:?}", dir))?;
+        }
+
+        for i in 1..=count {
+            let input = generator.generate(&mut rng)?;
+            match output_dir {
+                Some(dir) => {
+                    let path = dir.join(format!("input_{i}.txt"));
+                    std::fs::write(&path, input).with_context(|| format!("Unable to write {:?}", path))?;
+                }
+                None if i == 1 => println!("{input}"),
+                None => println!("\n{input}"),
+            }
+        }
+        Ok(())
+    }
+
+    fn check_tests(&self, args: &ArgMatches) -> Result<()> {
+        let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+            Some(h) => h.to_owned(),
+            None => self.current_handle()?,
+        };
+        let clash = self.read_clash(&handle)?;
+        let stub_generator = clash.stub_generator().context("Clash provides no input stub generator")?;
+        let mut checker = stub::InputChecker::new(stub_generator)?;
+        if !args.get_flag("no-constraints") {
+            checker.apply_constraints(&clash.variable_constraints());
+        }
+        let ostyle = OutputStyle::from_env(false);
+
+        let testcases = self.all_testcases(&handle, &clash)?;
+        let mut num_mismatched = 0;
+        for testcase in &testcases {
+            let mismatches = checker.check(&testcase.test_in);
+            if mismatches.is_empty() {
+                println!("{} {}", ostyle.success.paint("OK"), ostyle.styled_testcase_title(testcase));
+                continue
+            }
+            num_mismatched += 1;
+            println!("{} {}", ostyle.failure.paint("MISMATCH"), ostyle.styled_testcase_title(testcase));
+            for mismatch in mismatches {
+                println!(" {}", ostyle.stderr.paint(mismatch.to_string()));
+            }
+        }
+
+        match num_mismatched {
+            0 => Ok(()),
+            n => Err(anyhow!("{n}/{} testcase inputs don't match", testcases.len())),
+        }
+    }
+
+    fn diff_test(&self, args: &ArgMatches) -> Result<()> {
+        let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+            Some(h) => h.to_owned(),
+            None => self.current_handle()?,
+        };
+        let mut reference = command_from_argument(args.get_one::<String>("reference"))?
+            .context("--reference should not be empty")?;
+        let mut candidate = command_from_argument(args.get_one::<String>("candidate"))?
+            .context("--candidate should not be empty")?;
+        let limits = limits_from_args(args)?;
+        let comparator = match args.get_one::<Comparator>("compare") {
+            Some(comparator) => *comparator,
+            None => ClashSettings::load(&self.clash_settings_file)?
+                .comparator(&handle)?
+                .unwrap_or_default(),
+        };
+        let verifier = Verifier::Compare(comparator);
+        let generator = self.input_generator(&handle, args)?;
+        let mut rng = seeded_rng(args);
+        let runs = args.get_one::<NonZeroUsize>("runs").expect("--runs has a default value").get();
+        let max_shrinks = *args.get_one::<usize>("max-shrinks").expect("--max-shrinks has a default value");
+
+        // The testcase with the output of the reference as the expected output,
+        // None if the reference itself fails on the input
+        let mut expected = |input: &str| {
+            let testcase = Testcase {
+                index: 1,
+                title: String::from("Counterexample"),
+                test_in: input.to_string(),
+                test_out: String::new(),
+                is_validator: false,
+            };
+            let test_out = match solution::run_testcase(&testcase, &mut reference, &limits, &verifier) {
+                solution::TestResult::Success { .. } => String::new(),
+                solution::TestResult::WrongOutput { stdout, .. } => stdout.to_str_lossy().into_owned(),
+                _ => return None,
+            };
+            Some(Testcase { test_out, ..testcase })
+        };
+
+        let mut num_skipped = 0;
+        for run in 1..=runs {
+            let generated = generator.generate_shrinkable(&mut rng)?;
+            let Some(testcase) = expected(&generated.input) else {
+                num_skipped += 1;
+                continue
+            };
+            if solution::run_testcase(&testcase, &mut candidate, &limits, &verifier).is_success() {
+                continue
+            }
+
+            eprintln!("Outputs differ on input {run}, shrinking it...");
+            let shrunk = generator.shrink(&generated, max_shrinks, |input| match expected(input) {
+                Some(testcase) => {
+                    !solution::run_testcase(&testcase, &mut candidate, &limits, &verifier).is_success()
+                }
+                None => false,
+            })?;
+            let testcase = expected(&shrunk.input).context("Reference should pass the counterexample")?;
+            let result = solution::run_testcase(&testcase, &mut candidate, &limits, &verifier);
+            OutputStyle::from_env(true).print_result(&testcase, &result, &comparator);
+            return Err(anyhow!("Found a counterexample after {run} inputs"))
+        }
+
+        let num_compared = runs - num_skipped;
+        println!("No differences found in {num_compared} inputs");
+        if num_skipped > 0 {
+            println!("Skipped {num_skipped} inputs that the reference failed on");
+        }
+        Ok(())
+    }
+
     fn generate_stub(&self, args: &ArgMatches) -> Result<()> {
         let lang_arg = args
             .get_one::<String>("PROGRAMMING_LANGUAGE")
@@ -520,18 +1758,34 @@ fn main() -> Result<()> {
     // We look for the locally stored clashes here:
     let project_dirs = ProjectDirs::from("", "CoCtus", "coctus").expect("Unable to find project directory");
 
-    let app = App::new(project_dirs.data_dir(), project_dirs.config_dir());
+    let app = App::new(project_dirs.data_dir(), project_dirs.config_dir(), project_dirs.cache_dir());
 
     match cli().get_matches().subcommand() {
         Some(("show", args)) => app.show(args),
         Some(("next", args)) => app.next(args),
+        Some(("compare", args)) => app.compare(args),
+        Some(("solutions", args)) => app.solutions(args),
+        Some(("tests", args)) => app.tests(args),
+        Some(("history", args)) => app.history(args),
         Some(("status", args)) => app.status(args),
         Some(("run", args)) => app.run(args),
+        Some(("bench", args)) => app.bench(args),
         Some(("fetch", args)) => app.fetch(args),
         Some(("showtests", args)) => app.showtests(args),
         Some(("json", args)) => app.json(args),
         Some(("generate-stub", args)) => app.generate_stub(args),
+        Some(("gen-input", args)) => app.gen_input(args),
+        Some(("diff-test", args)) => app.diff_test(args),
+        Some(("check-tests", args)) => app.check_tests(args),
         Some(("generate-shell-completion", args)) => app.generate_completions(args),
         _ => Err(anyhow!("unimplemented subcommand")),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_cli_is_valid() {
+        super::cli().debug_assert();
+    }
+}
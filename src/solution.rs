@@ -1,12 +1,31 @@
+mod benchmark;
+mod comparator;
+mod exit_reason;
+mod golf;
+mod limits;
+mod output;
+mod resource_usage;
 mod test_result;
+mod verifier;
 
-use std::io::Write;
-use std::process::Command;
-use std::time::Duration;
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::num::NonZeroUsize;
+use std::process::{Child, Command};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::Instant;
 
+pub use benchmark::{benchmark_testcase, TimingStats};
+pub use comparator::{Annotation, Comparator, TokenMatch};
+pub use exit_reason::ExitReason;
+pub use golf::code_length;
+pub use limits::Limits;
+pub use output::{Output, OutputChunk};
+pub use resource_usage::ResourceUsage;
 use test_result::CommandExit;
 pub use test_result::TestResult;
-use wait_timeout::ChildExt;
+pub use verifier::{Checker, Verifier};
 
 use crate::clash::Testcase;
 
@@ -16,7 +35,7 @@ use crate::clash::Testcase;
 ///
 /// ```
 /// use clashlib::clash::Testcase;
-/// use clashlib::solution::lazy_run;
+/// use clashlib::solution::{lazy_run, Limits, Verifier};
 ///
 /// let testcases = [
 ///     Testcase {
@@ -28,9 +47,9 @@ use crate::clash::Testcase;
 ///     }
 /// ];
 /// let mut command = std::process::Command::new("cat");
-/// let timeout = std::time::Duration::from_secs(5);
+/// let limits = Limits::with_timeout(std::time::Duration::from_secs(5));
 ///
-/// for (testcase, test_result) in lazy_run(&testcases, &mut command, &timeout) {
+/// for (testcase, test_result) in lazy_run(&testcases, &mut command, &limits, &Verifier::default()) {
 ///     assert_eq!(testcase.title, "Test #1");
 ///     assert!(test_result.is_success());
 /// }
@@ -38,59 +57,278 @@ use crate::clash::Testcase;
 pub fn lazy_run<'a>(
     testcases: impl IntoIterator<Item = &'a Testcase>,
     run_command: &'a mut Command,
-    timeout: &'a Duration,
+    limits: &'a Limits,
+    verifier: &'a Verifier,
 ) -> impl IntoIterator<Item = (&'a Testcase, TestResult)> {
+    limits::apply_resource_limits(run_command, limits);
     testcases.into_iter().map(|test| {
-        let result = run_testcase(test, run_command, timeout);
+        let result = run_prepared_testcase(test, run_command, limits, verifier);
         (test, result)
     })
 }
 
+/// Run a command against testcases, running up to `jobs` of them at the same
+/// time. Results are yielded in the same order as the testcases regardless of
+/// which one finishes first.
+///
+/// New testcases are only started as results are consumed, so if the caller
+/// stops iterating (for example after the first failure) the remaining
+/// testcases are never run. Testcases that are already running when the
+/// iterator is dropped are allowed to finish.
+///
+/// # Examples
+///
+/// ```
+/// use std::num::NonZeroUsize;
+///
+/// use clashlib::clash::Testcase;
+/// use clashlib::solution::{parallel_run, Limits, Verifier};
+///
+/// let testcases: Vec<Testcase> = (1..=4)
+///     .map(|index| Testcase {
+///         index,
+///         title: format!("Test #{index}"),
+///         test_in: index.to_string(),
+///         test_out: index.to_string(),
+///         is_validator: false,
+///     })
+///     .collect();
+/// let mut command = std::process::Command::new("cat");
+/// let limits = Limits::with_timeout(std::time::Duration::from_secs(5));
+/// let jobs = NonZeroUsize::new(4).unwrap();
+///
+/// for (expected_index, (testcase, test_result)) in
+///     (1..).zip(parallel_run(&testcases, &mut command, &limits, &Verifier::default(), jobs))
+/// {
+///     assert_eq!(testcase.index, expected_index);
+///     assert!(test_result.is_success());
+/// }
+/// ```
+pub fn parallel_run<'a>(
+    testcases: impl IntoIterator<Item = &'a Testcase> + 'a,
+    run_command: &'a mut Command,
+    limits: &'a Limits,
+    verifier: &'a Verifier,
+    jobs: NonZeroUsize,
+) -> impl IntoIterator<Item = (&'a Testcase, TestResult)> {
+    limits::apply_resource_limits(run_command, limits);
+    ParallelRun {
+        testcases: testcases.into_iter(),
+        run_command,
+        limits: limits.clone(),
+        verifier: verifier.clone(),
+        jobs: jobs.get(),
+        running: VecDeque::new(),
+    }
+}
+
+/// Result of a testcase that has been started by [ParallelRun] but has not
+/// been yielded yet.
+enum PendingResult {
+    Running(JoinHandle<TestResult>),
+    Finished(TestResult),
+}
+
+impl PendingResult {
+    fn join(self) -> TestResult {
+        match self {
+            PendingResult::Running(handle) => handle.join().expect("Testcase runner thread should not panic"),
+            PendingResult::Finished(result) => result,
+        }
+    }
+}
+
+struct ParallelRun<'a, I: Iterator<Item = &'a Testcase>> {
+    testcases: I,
+    run_command: &'a mut Command,
+    limits: Limits,
+    verifier: Verifier,
+    jobs: usize,
+    running: VecDeque<(&'a Testcase, PendingResult)>,
+}
+
+impl<'a, I: Iterator<Item = &'a Testcase>> ParallelRun<'a, I> {
+    fn start(&mut self, testcase: &Testcase) -> PendingResult {
+        // Processes are spawned from this thread because they all share the
+        // same `Command`, only waiting for them happens in the background.
+        match spawn_solution(self.run_command) {
+            Ok(spawned) => {
+                let testcase = testcase.clone();
+                let limits = self.limits.clone();
+                let verifier = self.verifier.clone();
+                PendingResult::Running(std::thread::spawn(move || {
+                    wait_for_solution(&testcase, spawned, &limits, &verifier)
+                }))
+            }
+            Err(result) => PendingResult::Finished(result),
+        }
+    }
+}
+
+impl<'a, I: Iterator<Item = &'a Testcase>> Iterator for ParallelRun<'a, I> {
+    type Item = (&'a Testcase, TestResult);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.running.len() < self.jobs {
+            let Some(testcase) = self.testcases.next() else {
+                break
+            };
+            let pending = self.start(testcase);
+            self.running.push_back((testcase, pending));
+        }
+        let (testcase, pending) = self.running.pop_front()?;
+        Some((testcase, pending.join()))
+    }
+}
+
+impl<'a, I: Iterator<Item = &'a Testcase>> Drop for ParallelRun<'a, I> {
+    fn drop(&mut self) {
+        // Wait for the testcases that are still running so that their
+        // processes don't outlive the run.
+        for (_, pending) in self.running.drain(..) {
+            pending.join();
+        }
+    }
+}
+
 /// Run a command against a single testcase.
-pub fn run_testcase(testcase: &Testcase, run_command: &mut Command, timeout: &Duration) -> TestResult {
-    let mut run = match run_command
+pub fn run_testcase(
+    testcase: &Testcase,
+    run_command: &mut Command,
+    limits: &Limits,
+    verifier: &Verifier,
+) -> TestResult {
+    limits::apply_resource_limits(run_command, limits);
+    run_prepared_testcase(testcase, run_command, limits, verifier)
+}
+
+/// Run a command that already has resource limits applied against a single
+/// testcase.
+fn run_prepared_testcase(
+    testcase: &Testcase,
+    run_command: &mut Command,
+    limits: &Limits,
+    verifier: &Verifier,
+) -> TestResult {
+    match spawn_solution(run_command) {
+        Ok(spawned) => wait_for_solution(testcase, spawned, limits, verifier),
+        Err(result) => result,
+    }
+}
+
+/// Start the solution. On Unix-like platforms it becomes the leader of a new
+/// process group so that any processes it starts can be killed along with it.
+#[allow(clippy::result_large_err)]
+fn spawn_solution(run_command: &mut Command) -> Result<(Child, Instant), TestResult> {
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(run_command, 0);
+
+    let started = Instant::now();
+    run_command
         .stdin(std::process::Stdio::piped())
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
         .spawn()
-    {
-        Ok(run) => run,
-        Err(error) => {
+        .map(|child| (child, started))
+        .map_err(|error| {
             let program = run_command.get_program().to_str().unwrap_or("Unable to run command");
             let error_msg = format!("{}: {}", program, error);
-            return TestResult::UnableToRun { error_msg }
-        }
-    };
-
-    run.stdin
-        .as_mut()
-        .expect("STDIN of child process should be captured")
-        .write_all(testcase.test_in.as_bytes())
-        .expect("STDIN of child process should be writable");
+            TestResult::UnableToRun { error_msg }
+        })
+}
 
-    let timed_out = run
-        .wait_timeout(*timeout)
-        .expect("Process should be able to wait for execution")
-        .is_none();
+fn wait_for_solution(
+    testcase: &Testcase,
+    (mut run, started): (Child, Instant),
+    limits: &Limits,
+    verifier: &Verifier,
+) -> TestResult {
+    let stdin = run.stdin.take().expect("STDIN of child process should be captured");
+    let stdout_pipe = run.stdout.take().expect("STDOUT of child process should be captured");
+    let stderr_pipe = run.stderr.take().expect("STDERR of child process should be captured");
 
-    if timed_out {
-        run.kill().expect("Process should have been killed");
-    }
+    // Feeding the input and draining the outputs all happen at the same time
+    // so that a solution that prints a lot before reading all of its input
+    // can't fill up a pipe and deadlock.
+    let output_exceeded = AtomicBool::new(false);
+    let (exited, stdout, stderr) = std::thread::scope(|scope| {
+        scope.spawn(|| feed_input(stdin, testcase.test_in.as_bytes()));
+        let stdout_reader = scope.spawn(|| read_output(stdout_pipe, limits.output, &output_exceeded));
+        let stderr_reader = scope.spawn(|| read_output(stderr_pipe, limits.output, &output_exceeded));
 
-    let output = run.wait_with_output().expect("Process should allow waiting for its execution");
+        let exited = resource_usage::wait_with_usage(&mut run, started, limits.timeout, &output_exceeded);
+        let stdout = stdout_reader.join().expect("STDOUT reader thread should not panic");
+        let stderr = stderr_reader.join().expect("STDERR reader thread should not panic");
+        (exited, stdout, stderr)
+    });
 
-    let exit_status = if timed_out {
+    let exit_status = if output_exceeded.load(Ordering::Relaxed) {
+        CommandExit::OutputLimitExceeded
+    } else if exited.timed_out {
         CommandExit::Timeout
-    } else if output.status.success() {
+    } else if exited.status.success() {
         CommandExit::Ok
+    } else if limits::hit_cpu_limit(limits, &exited.status, &exited.usage) {
+        CommandExit::CpuLimitExceeded
+    } else if limits::hit_memory_limit(limits, &exited.status, &exited.usage, &stderr) {
+        CommandExit::MemoryLimitExceeded
     } else {
-        CommandExit::Error
+        CommandExit::Error(ExitReason::from_status(&exited.status))
+    };
+    let verdict = match exit_status {
+        // Don't bother verifying output that is known to be incomplete
+        CommandExit::OutputLimitExceeded => verifier::Verdict::Rejected { message: None },
+        _ => verifier.verify(testcase, &stdout),
+    };
+    TestResult::from_verdict(verdict, stdout, stderr, exit_status).with_usage(exited.usage)
+}
+
+fn feed_input(mut stdin: std::process::ChildStdin, input: &[u8]) {
+    match stdin.write_all(input) {
+        // The solution exited (or was killed) without reading all of its input
+        Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => {}
+        result => result.expect("STDIN of child process should be writable"),
+    }
+}
+
+/// Read everything from `pipe`. If the output grows past `limit` bytes it is
+/// truncated and `exceeded` is set, which tells the thread waiting for the
+/// process to kill it.
+fn read_output(mut pipe: impl Read, limit: Option<usize>, exceeded: &AtomicBool) -> Vec<u8> {
+    let Some(limit) = limit else {
+        let mut output = Vec::new();
+        pipe.read_to_end(&mut output).expect("Output of child process should be readable");
+        return output
     };
-    TestResult::from_output(&testcase.test_out, output.stdout, output.stderr, exit_status)
+
+    let mut output = Vec::new();
+    // Read one byte past the limit to find out whether it was exceeded
+    let mut limited_pipe = pipe.by_ref().take(limit as u64 + 1);
+    limited_pipe
+        .read_to_end(&mut output)
+        .expect("Output of child process should be readable");
+    if output.len() <= limit {
+        return output
+    }
+
+    output.truncate(limit);
+    // Don't leave half of a multi-byte character at the end
+    if let Err(err) = std::str::from_utf8(&output) {
+        if err.error_len().is_none() {
+            output.truncate(err.valid_up_to());
+        }
+    }
+    exceeded.store(true, Ordering::Relaxed);
+    // Dropping the pipe makes further writes to it fail, which stops the
+    // solution even before it gets killed.
+    drop(pipe);
+    output
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use super::*;
 
     #[test]
@@ -99,8 +337,8 @@ mod tests {
         let mut run_cmd = Command::new("tr");
         run_cmd.arg("X");
         run_cmd.arg("b");
-        let timeout = Duration::from_secs(1);
-        assert!(lazy_run(clash.testcases(), &mut run_cmd, &timeout)
+        let limits = Limits::with_timeout(Duration::from_secs(1));
+        assert!(lazy_run(clash.testcases(), &mut run_cmd, &limits, &Verifier::default())
             .into_iter()
             .all(|(_, test_result)| test_result.is_success()))
     }
@@ -108,10 +346,354 @@ mod tests {
     #[test]
     fn test_failing_solution() {
         let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
-        let timeout = Duration::from_secs(1);
+        let limits = Limits::with_timeout(Duration::from_secs(1));
         let mut run_cmd = Command::new("cat");
-        assert!(lazy_run(clash.testcases(), &mut run_cmd, &timeout)
+        assert!(lazy_run(clash.testcases(), &mut run_cmd, &limits, &Verifier::default())
             .into_iter()
             .all(|(_, test_result)| !test_result.is_success()))
     }
+
+    #[test]
+    fn test_parallel_run_preserves_order() {
+        let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
+        let mut run_cmd = Command::new("tr");
+        run_cmd.arg("X");
+        run_cmd.arg("b");
+        let limits = Limits::with_timeout(Duration::from_secs(1));
+        let jobs = NonZeroUsize::new(3).unwrap();
+        let indices: Vec<usize> =
+            parallel_run(clash.testcases(), &mut run_cmd, &limits, &Verifier::default(), jobs)
+                .into_iter()
+                .map(|(testcase, test_result)| {
+                    assert!(test_result.is_success());
+                    testcase.index
+                })
+                .collect();
+        assert_eq!(indices, (1..=clash.testcases().len()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_parallel_run_runs_concurrently() {
+        let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
+        let mut run_cmd = Command::new("sleep");
+        run_cmd.arg("0.5");
+        let limits = Limits::with_timeout(Duration::from_secs(5));
+        let jobs = NonZeroUsize::new(clash.testcases().len()).unwrap();
+        let verifier = Verifier::default();
+        let start = std::time::Instant::now();
+        let results: Vec<_> = parallel_run(clash.testcases(), &mut run_cmd, &limits, &verifier, jobs)
+            .into_iter()
+            .collect();
+        assert_eq!(results.len(), clash.testcases().len());
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cpu_limit_exceeded() {
+        let testcase = Testcase {
+            index: 1,
+            title: String::from("Busy loop"),
+            test_in: String::new(),
+            test_out: String::from("done"),
+            is_validator: false,
+        };
+        let mut run_cmd = Command::new("sh");
+        run_cmd.args(["-c", "while :; do :; done"]);
+        let limits = Limits {
+            timeout: Duration::from_secs(10),
+            cpu_time: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+        let result = run_testcase(&testcase, &mut run_cmd, &limits, &Verifier::default());
+        assert!(matches!(result, TestResult::CpuLimitExceeded { .. }), "{:?}", result);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_killed_solution_did_not_hit_cpu_limit() {
+        let testcase = Testcase {
+            index: 1,
+            title: String::from("Killed"),
+            test_in: String::new(),
+            test_out: String::from("done"),
+            is_validator: false,
+        };
+        let mut run_cmd = Command::new("sh");
+        run_cmd.args(["-c", "kill -KILL $$"]);
+        let limits = Limits {
+            timeout: Duration::from_secs(10),
+            cpu_time: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+        let result = run_testcase(&testcase, &mut run_cmd, &limits, &Verifier::default());
+        assert!(matches!(result, TestResult::RuntimeError { .. }), "{:?}", result);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_memory_limit_exceeded() {
+        let testcase = Testcase {
+            index: 1,
+            title: String::from("Memory hog"),
+            test_in: String::new(),
+            test_out: String::from("done"),
+            is_validator: false,
+        };
+        let limits = Limits {
+            timeout: Duration::from_secs(10),
+            memory: Some(32 * 1024 * 1024),
+            ..Default::default()
+        };
+        // Filling up the memory bit by bit
+        let mut run_cmd = Command::new("awk");
+        run_cmd.arg(
+            "BEGIN { chunk = \"x\"; for (i = 0; i < 20; i++) chunk = chunk chunk; while (1) s = s chunk }",
+        );
+        let result = run_testcase(&testcase, &mut run_cmd, &limits, &Verifier::default());
+        assert!(matches!(result, TestResult::MemoryLimitExceeded { .. }), "{:?}", result);
+
+        // A crash is reported as a crash
+        let mut run_cmd = Command::new("perl");
+        run_cmd.args(["-e", "unpack('p', pack('L!', 1))"]);
+        let result = run_testcase(&testcase, &mut run_cmd, &limits, &Verifier::default());
+        let TestResult::RuntimeError { exit, .. } = result else {
+            panic!("expected TestResult::RuntimeError but found {:?}", result)
+        };
+        assert_eq!(exit, ExitReason::Signal(libc::SIGSEGV));
+
+        // Failing for another reason is not evidence of running out of memory
+        // even if the message says so
+        let mut run_cmd = Command::new("sh");
+        run_cmd.args(["-c", "echo 'out of memory' >&2; exit 1"]);
+        let result = run_testcase(&testcase, &mut run_cmd, &limits, &Verifier::default());
+        assert!(matches!(result, TestResult::RuntimeError { .. }), "{:?}", result);
+        let mut run_cmd = Command::new("sh");
+        run_cmd.args(["-c", "echo 'assertion failed' >&2; kill -ABRT $$"]);
+        let result = run_testcase(&testcase, &mut run_cmd, &limits, &Verifier::default());
+        assert!(matches!(result, TestResult::RuntimeError { .. }), "{:?}", result);
+        let mut run_cmd = Command::new("sh");
+        run_cmd.args([
+            "-c",
+            "echo 'memory allocation of 1048576 bytes failed' >&2; kill -ABRT $$",
+        ]);
+        let result = run_testcase(&testcase, &mut run_cmd, &limits, &Verifier::default());
+        assert!(matches!(result, TestResult::MemoryLimitExceeded { .. }), "{:?}", result);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_runtime_error_reports_signal() {
+        let testcase = Testcase {
+            index: 1,
+            title: String::from("Abort"),
+            test_in: String::new(),
+            test_out: String::from("done"),
+            is_validator: false,
+        };
+        let mut run_cmd = Command::new("sh");
+        run_cmd.args(["-c", "kill -ABRT $$"]);
+        let limits = Limits::with_timeout(Duration::from_secs(5));
+        let result = run_testcase(&testcase, &mut run_cmd, &limits, &Verifier::default());
+        let TestResult::RuntimeError { exit, .. } = result else {
+            panic!("expected TestResult::RuntimeError but found {:?}", result)
+        };
+        assert_eq!(exit, ExitReason::Signal(libc::SIGABRT));
+
+        run_cmd = Command::new("sh");
+        run_cmd.args(["-c", "exit 3"]);
+        let result = run_testcase(&testcase, &mut run_cmd, &limits, &Verifier::default());
+        assert!(
+            matches!(
+                result,
+                TestResult::RuntimeError {
+                    exit: ExitReason::Code(3),
+                    ..
+                }
+            ),
+            "{:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_resource_usage_is_recorded() {
+        let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
+        let mut run_cmd = Command::new("sh");
+        run_cmd.args(["-c", "sleep 0.2; tr X b"]);
+        let limits = Limits::with_timeout(Duration::from_secs(5));
+        let testcase = &clash.testcases()[0];
+        let result = run_testcase(testcase, &mut run_cmd, &limits, &Verifier::default());
+        assert!(result.is_success());
+        let usage = result.usage().expect("solution should have been run");
+        assert!(usage.wall_time >= Duration::from_millis(200));
+        #[cfg(unix)]
+        assert!(usage.max_rss.unwrap() > 0);
+    }
+
+    fn large_testcase() -> Testcase {
+        let line = "abcdefghijklmnopqrstuvwxyz".repeat(10);
+        let text = std::iter::repeat(line).take(20_000).collect::<Vec<_>>().join("\n");
+        Testcase {
+            index: 1,
+            title: String::from("Large"),
+            test_in: text.clone(),
+            test_out: text,
+            is_validator: false,
+        }
+    }
+
+    #[test]
+    fn test_large_input_and_output_does_not_deadlock() {
+        let testcase = large_testcase();
+        let mut run_cmd = Command::new("cat");
+        let limits = Limits::with_timeout(Duration::from_secs(10));
+        let result = run_testcase(&testcase, &mut run_cmd, &limits, &Verifier::default());
+        assert!(result.is_success(), "{:?}", result.usage());
+    }
+
+    #[test]
+    fn test_output_before_reading_input_does_not_deadlock() {
+        let testcase = large_testcase();
+        let mut run_cmd = Command::new("sh");
+        // Print a lot to both STDOUT and STDERR before reading anything
+        run_cmd.args([
+            "-c",
+            "yes | head -c 1000000; yes | head -c 1000000 >&2; cat > /dev/null",
+        ]);
+        let limits = Limits::with_timeout(Duration::from_secs(10));
+        let result = run_testcase(&testcase, &mut run_cmd, &limits, &Verifier::default());
+        assert!(matches!(result, TestResult::WrongOutput { .. }));
+    }
+
+    #[test]
+    fn test_solution_that_ignores_large_input() {
+        let testcase = large_testcase();
+        let mut run_cmd = Command::new("echo");
+        let limits = Limits::with_timeout(Duration::from_secs(10));
+        let result = run_testcase(&testcase, &mut run_cmd, &limits, &Verifier::default());
+        assert!(matches!(result, TestResult::WrongOutput { .. }));
+    }
+
+    #[test]
+    fn test_output_limit_exceeded() {
+        let testcase = Testcase {
+            index: 1,
+            title: String::from("Infinite loop"),
+            test_in: String::new(),
+            test_out: String::from("y"),
+            is_validator: false,
+        };
+        let mut run_cmd = Command::new("yes");
+        let limits = Limits {
+            timeout: Duration::from_secs(10),
+            output: Some(1000),
+            ..Default::default()
+        };
+        let start = std::time::Instant::now();
+        let result = run_testcase(&testcase, &mut run_cmd, &limits, &Verifier::default());
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "solution should be killed before the timeout"
+        );
+        match result {
+            TestResult::OutputLimitExceeded { stdout, .. } => assert!(stdout.len() <= 1000),
+            other => panic!("expected TestResult::OutputLimitExceeded but found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_output_limit_exceeded_after_exit() {
+        let testcase = Testcase {
+            index: 1,
+            title: String::from("Exits before its output is read"),
+            test_in: String::new(),
+            test_out: String::from("y"),
+            is_validator: false,
+        };
+        // Fits in the pipe buffer so the solution exits before it is read
+        let mut run_cmd = Command::new("sh");
+        run_cmd.args(["-c", "yes | head -c 5000"]);
+        let limits = Limits {
+            timeout: Duration::from_secs(10),
+            output: Some(1000),
+            ..Default::default()
+        };
+        let result = run_testcase(&testcase, &mut run_cmd, &limits, &Verifier::default());
+        assert!(matches!(result, TestResult::OutputLimitExceeded { .. }), "{:?}", result);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_timeout_kills_child_processes() {
+        let testcase = Testcase {
+            index: 1,
+            title: String::from("Wrapper"),
+            test_in: String::new(),
+            test_out: String::from("done"),
+            is_validator: false,
+        };
+        // The sleeping grandchild inherits the STDOUT of the wrapper script
+        let mut run_cmd = Command::new("sh");
+        run_cmd.args(["-c", "sleep 30 & wait"]);
+        let limits = Limits::with_timeout(Duration::from_millis(500));
+        let start = std::time::Instant::now();
+        let result = run_testcase(&testcase, &mut run_cmd, &limits, &Verifier::default());
+        assert!(matches!(result, TestResult::Timeout { .. }), "{:?}", result);
+        assert!(start.elapsed() < Duration::from_secs(5), "child of the solution should be killed");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_child_processes_do_not_outlive_solution() {
+        let testcase = Testcase {
+            index: 1,
+            title: String::from("Background process"),
+            test_in: String::new(),
+            test_out: String::from("done"),
+            is_validator: false,
+        };
+        let mut run_cmd = Command::new("sh");
+        run_cmd.args(["-c", "sleep 30 & echo done"]);
+        let limits = Limits::with_timeout(Duration::from_secs(10));
+        let start = std::time::Instant::now();
+        let result = run_testcase(&testcase, &mut run_cmd, &limits, &Verifier::default());
+        assert!(result.is_success(), "{:?}", result);
+        assert!(start.elapsed() < Duration::from_secs(5), "child of the solution should be killed");
+    }
+
+    #[test]
+    fn test_invalid_utf8_output_is_preserved() {
+        let testcase = Testcase {
+            index: 1,
+            title: String::from("Invalid UTF-8"),
+            test_in: String::new(),
+            test_out: String::from("ok"),
+            is_validator: false,
+        };
+        let mut run_cmd = Command::new("printf");
+        run_cmd.arg("o\\377k");
+        let limits = Limits::with_timeout(Duration::from_secs(5));
+        let result = run_testcase(&testcase, &mut run_cmd, &limits, &Verifier::default());
+        let TestResult::WrongOutput { stdout, .. } = result else {
+            panic!("expected TestResult::WrongOutput but found {:?}", result)
+        };
+        assert_eq!(stdout.as_bytes(), b"o\xFFk");
+        assert_eq!(stdout.invalid_utf8(), vec![1..2]);
+    }
+
+    #[test]
+    fn test_checker_accepts_different_output() {
+        let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
+        let mut run_cmd = Command::new("tr");
+        run_cmd.args(["X", "B"]);
+        // Accept the output if it matches the expected output case-insensitively
+        let script = r#"test "$(tr a-z A-Z < "$2")" = "$(tr a-z A-Z < "$3")""#;
+        let checker = Checker::new("sh", vec!["-c".into(), script.into(), "checker".into()]);
+        let limits = Limits::with_timeout(Duration::from_secs(1));
+        let verifier = Verifier::Checker(checker);
+        assert!(lazy_run(clash.testcases(), &mut run_cmd, &limits, &verifier)
+            .into_iter()
+            .all(|(_, test_result)| test_result.is_success()))
+    }
 }
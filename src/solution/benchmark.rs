@@ -0,0 +1,136 @@
+use std::process::Command;
+use std::time::Duration;
+
+use super::{limits, run_prepared_testcase, Limits, TestResult, Verifier};
+use crate::clash::Testcase;
+
+/// Summary of the wall clock times of repeated runs of a solution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimingStats {
+    pub runs: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub p95: Duration,
+}
+
+impl TimingStats {
+    /// Compute statistics over `timings`. Returns `None` if there are no
+    /// timings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use clashlib::solution::TimingStats;
+    ///
+    /// let timings: Vec<Duration> = (1..=20).map(Duration::from_millis).collect();
+    /// let stats = TimingStats::from_timings(&timings).unwrap();
+    /// assert_eq!(stats.min, Duration::from_millis(1));
+    /// assert_eq!(stats.median, Duration::from_millis(10));
+    /// assert_eq!(stats.p95, Duration::from_millis(19));
+    /// ```
+    pub fn from_timings(timings: &[Duration]) -> Option<Self> {
+        let mut sorted = timings.to_vec();
+        sorted.sort();
+        Some(TimingStats {
+            runs: sorted.len(),
+            min: *sorted.first()?,
+            median: percentile(&sorted, 50),
+            p95: percentile(&sorted, 95),
+        })
+    }
+}
+
+// Nearest-rank percentile of a sorted, non-empty slice.
+fn percentile(sorted: &[Duration], pct: usize) -> Duration {
+    let rank = (pct * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// Run a command against a single testcase `warmup + runs` times and collect
+/// the wall clock times of the last `runs` executions.
+///
+/// Returns the first [TestResult] that was not a success if the solution
+/// fails on any of the runs (including the warm-up runs).
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::clash::Testcase;
+/// use clashlib::solution::{benchmark_testcase, Limits, Verifier};
+///
+/// let testcase = Testcase {
+///     index: 1,
+///     title: String::from("Test #1"),
+///     test_in: String::from("hey"),
+///     test_out: String::from("hey"),
+///     is_validator: false,
+/// };
+/// let mut command = std::process::Command::new("cat");
+/// let limits = Limits::with_timeout(std::time::Duration::from_secs(5));
+///
+/// let timings = benchmark_testcase(&testcase, &mut command, &limits, &Verifier::default(), 1, 3).unwrap();
+/// assert_eq!(timings.len(), 3);
+/// ```
+#[allow(clippy::result_large_err)]
+pub fn benchmark_testcase(
+    testcase: &Testcase,
+    run_command: &mut Command,
+    limits: &Limits,
+    verifier: &Verifier,
+    warmup: usize,
+    runs: usize,
+) -> Result<Vec<Duration>, TestResult> {
+    limits::apply_resource_limits(run_command, limits);
+    let mut timings = Vec::with_capacity(runs);
+    for run in 0..warmup + runs {
+        let result = run_prepared_testcase(testcase, run_command, limits, verifier);
+        if !result.is_success() {
+            return Err(result)
+        }
+        if run >= warmup {
+            let usage = result.usage().expect("successful run should have resource usage");
+            timings.push(usage.wall_time);
+        }
+    }
+    Ok(timings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_of_single_timing() {
+        let stats = TimingStats::from_timings(&[Duration::from_millis(7)]).unwrap();
+        assert_eq!(stats.min, Duration::from_millis(7));
+        assert_eq!(stats.median, Duration::from_millis(7));
+        assert_eq!(stats.p95, Duration::from_millis(7));
+    }
+
+    #[test]
+    fn test_percentiles_are_order_independent() {
+        let timings = [5, 1, 4, 2, 3].map(Duration::from_millis);
+        let stats = TimingStats::from_timings(&timings).unwrap();
+        assert_eq!(stats.runs, 5);
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.median, Duration::from_millis(3));
+        assert_eq!(stats.p95, Duration::from_millis(5));
+    }
+
+    #[test]
+    fn test_no_timings() {
+        assert_eq!(TimingStats::from_timings(&[]), None);
+    }
+
+    #[test]
+    fn test_benchmark_failing_solution() {
+        let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
+        let mut run_cmd = Command::new("cat");
+        let limits = Limits::with_timeout(Duration::from_secs(1));
+        let result =
+            benchmark_testcase(&clash.testcases()[0], &mut run_cmd, &limits, &Verifier::default(), 0, 3);
+        assert!(matches!(result, Err(TestResult::WrongOutput { .. })));
+    }
+}
@@ -0,0 +1,415 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use super::test_result::normalize_output;
+
+/// Built-in ways of comparing the output of a solution to the expected
+/// output.
+///
+/// # Examples
+///
+/// ```
+/// use std::str::FromStr;
+///
+/// use clashlib::solution::Comparator;
+///
+/// let float = Comparator::from_str("float:1e-3").unwrap();
+/// assert!(float.compare("3.1416", "3.14159"));
+/// assert!(!Comparator::Exact.compare("3.1416", "3.14159"));
+///
+/// let unordered = Comparator::from_str("unordered-lines").unwrap();
+/// assert!(unordered.compare("a\nb\nc", "c\na\nb"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Comparator {
+    /// Output must match exactly, ignoring trailing whitespace and
+    /// differences in line endings.
+    #[default]
+    Exact,
+    /// Output must consist of the same whitespace separated tokens. The
+    /// amount and kind of whitespace between them does not matter.
+    Tokens,
+    /// Like [Comparator::Exact] but ignoring the case of letters.
+    CaseInsensitive,
+    /// Output must consist of the same lines in any order.
+    UnorderedLines,
+    /// Like [Comparator::Tokens] but tokens that are numbers only need to be
+    /// within `abs_epsilon` or within `rel_epsilon` times the expected value
+    /// of the expected number.
+    Float { abs_epsilon: f64, rel_epsilon: f64 },
+}
+
+/// A token (or line) of the actual output and whether it satisfied the
+/// comparator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenMatch {
+    /// Byte range of the token in the actual output.
+    pub range: Range<usize>,
+    pub matches: bool,
+}
+
+/// Result of matching the actual output against the expected output token
+/// by token. Used for highlighting the parts of the output that are wrong.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Annotation {
+    pub tokens: Vec<TokenMatch>,
+    /// Number of expected tokens (or lines) that are missing from the actual
+    /// output.
+    pub missing: usize,
+}
+
+impl Comparator {
+    pub const DEFAULT_EPSILON: f64 = 1e-6;
+
+    /// Returns true if `actual` is an acceptable output when `expected` is the
+    /// expected output.
+    pub fn compare(&self, expected: &str, actual: &str) -> bool {
+        let expected = normalize_output(expected);
+        let actual = normalize_output(actual);
+        match self {
+            Comparator::Exact => expected == actual,
+            Comparator::CaseInsensitive => expected.to_lowercase() == actual.to_lowercase(),
+            Comparator::Tokens | Comparator::Float { .. } | Comparator::UnorderedLines => {
+                let annotation = self.annotate(&expected, &actual);
+                annotation.missing == 0 && annotation.tokens.iter().all(|token| token.matches)
+            }
+        }
+    }
+
+    /// Pair up the tokens of `actual` with the tokens of `expected` and check
+    /// each pair separately.
+    ///
+    /// [Comparator::Exact] and [Comparator::UnorderedLines] use whole lines as
+    /// tokens. [Comparator::CaseInsensitive] pairs up lines first and only
+    /// then their tokens, so that differences in whitespace are noticed.
+    /// `actual` should already be normalized (see [Comparator::compare]) for
+    /// the ranges of the tokens to be useful.
+    pub fn annotate(&self, expected: &str, actual: &str) -> Annotation {
+        let expected = normalize_output(expected);
+        let actual = actual.trim_end();
+        match self {
+            Comparator::Exact => annotate_positionally(lines(&expected), lines(actual), |e, a| e == a),
+            Comparator::UnorderedLines => annotate_unordered_lines(&expected, actual),
+            Comparator::CaseInsensitive => annotate_case_insensitive(&expected, actual),
+            Comparator::Tokens => annotate_positionally(tokens(&expected), tokens(actual), |e, a| e == a),
+            Comparator::Float {
+                abs_epsilon,
+                rel_epsilon,
+            } => annotate_positionally(tokens(&expected), tokens(actual), |e, a| {
+                if e == a {
+                    return true
+                }
+                // Words like "nan" and "inf" parse as numbers too but they
+                // can only match themselves
+                match (e.parse::<f64>(), a.parse::<f64>()) {
+                    (Ok(e), Ok(a)) if e.is_finite() && a.is_finite() => {
+                        let diff = (e - a).abs();
+                        diff <= *abs_epsilon || diff <= rel_epsilon * e.abs()
+                    }
+                    _ => false,
+                }
+            }),
+        }
+    }
+}
+
+/// Whitespace separated tokens of `text` along with their byte ranges.
+fn tokens(text: &str) -> Vec<(&str, Range<usize>)> {
+    let mut result = Vec::new();
+    let mut start = None;
+    for (idx, ch) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        match (start, ch.is_whitespace()) {
+            (None, false) => start = Some(idx),
+            (Some(token_start), true) => {
+                result.push((&text[token_start..idx], token_start..idx));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+/// Lines of `text` (without line endings) along with their byte ranges.
+fn lines(text: &str) -> Vec<(&str, Range<usize>)> {
+    let mut result = Vec::new();
+    let mut start = 0;
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        result.push((content, start..start + content.len()));
+        start += line.len();
+    }
+    result
+}
+
+fn annotate_positionally(
+    expected: Vec<(&str, Range<usize>)>,
+    actual: Vec<(&str, Range<usize>)>,
+    matches: impl Fn(&str, &str) -> bool,
+) -> Annotation {
+    let missing = expected.len().saturating_sub(actual.len());
+    let mut expected = expected.into_iter();
+    let tokens = actual
+        .into_iter()
+        .map(|(actual_token, range)| TokenMatch {
+            range,
+            matches: expected
+                .next()
+                .is_some_and(|(expected_token, _)| matches(expected_token, actual_token)),
+        })
+        .collect();
+    Annotation { tokens, missing }
+}
+
+fn annotate_case_insensitive(expected: &str, actual: &str) -> Annotation {
+    let expected_lines = lines(expected);
+    let actual_lines = lines(actual);
+    let mut annotation = Annotation {
+        tokens: Vec::new(),
+        missing: expected_lines.len().saturating_sub(actual_lines.len()),
+    };
+    let mut expected_lines = expected_lines.into_iter();
+    for (actual_line, line_range) in actual_lines {
+        let offset = line_range.start;
+        let line_tokens = tokens(actual_line)
+            .into_iter()
+            .map(|(token, range)| (token, range.start + offset..range.end + offset));
+        let expected_line = expected_lines.next().map_or("", |(line, _)| line);
+        if expected_line.to_lowercase() == actual_line.to_lowercase() {
+            annotation
+                .tokens
+                .extend(line_tokens.map(|(_, range)| TokenMatch { range, matches: true }));
+            continue
+        }
+        let line_annotation = annotate_positionally(tokens(expected_line), line_tokens.collect(), |e, a| {
+            e.to_lowercase() == a.to_lowercase()
+        });
+        // If every token matches the line must differ in whitespace only, in
+        // which case the whole line is highlighted.
+        let only_whitespace_differs =
+            line_annotation.missing == 0 && line_annotation.tokens.iter().all(|token| token.matches);
+        annotation.tokens.extend(line_annotation.tokens.into_iter().map(|token| TokenMatch {
+            matches: token.matches && !only_whitespace_differs,
+            ..token
+        }));
+    }
+    annotation
+}
+
+fn annotate_unordered_lines(expected: &str, actual: &str) -> Annotation {
+    let mut remaining: HashMap<&str, usize> = HashMap::new();
+    for (line, _) in lines(expected) {
+        *remaining.entry(line.trim_end()).or_default() += 1;
+    }
+    let tokens = lines(actual)
+        .into_iter()
+        .map(|(line, range)| {
+            let matches = match remaining.get_mut(line.trim_end()) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    true
+                }
+                _ => false,
+            };
+            TokenMatch { range, matches }
+        })
+        .collect();
+    Annotation {
+        tokens,
+        missing: remaining.values().sum(),
+    }
+}
+
+impl FromStr for Comparator {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, options) = match s.split_once(':') {
+            Some((name, options)) => (name, Some(options)),
+            None => (s, None),
+        };
+        let comparator = match (name, options) {
+            ("exact", None) => Comparator::Exact,
+            ("tokens", None) => Comparator::Tokens,
+            ("case-insensitive", None) => Comparator::CaseInsensitive,
+            ("unordered-lines", None) => Comparator::UnorderedLines,
+            ("float", None) => Comparator::Float {
+                abs_epsilon: Self::DEFAULT_EPSILON,
+                rel_epsilon: Self::DEFAULT_EPSILON,
+            },
+            ("float", Some(options)) => parse_float_options(options)?,
+            (name, Some(_)) if name != "float" => return Err(anyhow!("comparator '{name}' takes no options")),
+            _ => {
+                return Err(anyhow!(
+                    "unknown comparator '{name}' (expected one of: exact, tokens, case-insensitive, \
+                     unordered-lines, float)"
+                ))
+            }
+        };
+        Ok(comparator)
+    }
+}
+
+// Parses the options of "float:1e-6", "float:abs=1e-6" or
+// "float:abs=1e-6,rel=1e-9"
+fn parse_float_options(options: &str) -> anyhow::Result<Comparator> {
+    let parse_epsilon = |value: &str| -> anyhow::Result<f64> {
+        match value.parse::<f64>() {
+            Ok(eps) if eps >= 0.0 => Ok(eps),
+            _ => Err(anyhow!("invalid epsilon '{value}' (expected a non-negative number)")),
+        }
+    };
+
+    if !options.contains('=') {
+        let epsilon = parse_epsilon(options)?;
+        return Ok(Comparator::Float {
+            abs_epsilon: epsilon,
+            rel_epsilon: epsilon,
+        })
+    }
+
+    let mut abs_epsilon = 0.0;
+    let mut rel_epsilon = 0.0;
+    for option in options.split(',') {
+        match option.split_once('=') {
+            Some(("abs", value)) => abs_epsilon = parse_epsilon(value)?,
+            Some(("rel", value)) => rel_epsilon = parse_epsilon(value)?,
+            _ => {
+                return Err(anyhow!(
+                    "invalid float comparator option '{option}' (expected abs=EPS or rel=EPS)"
+                ))
+            }
+        }
+    }
+    Ok(Comparator::Float {
+        abs_epsilon,
+        rel_epsilon,
+    })
+}
+
+impl std::fmt::Display for Comparator {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Comparator::Exact => write!(f, "exact"),
+            Comparator::Tokens => write!(f, "tokens"),
+            Comparator::CaseInsensitive => write!(f, "case-insensitive"),
+            Comparator::UnorderedLines => write!(f, "unordered-lines"),
+            Comparator::Float {
+                abs_epsilon,
+                rel_epsilon,
+            } if abs_epsilon == rel_epsilon => write!(f, "float:{abs_epsilon:e}"),
+            Comparator::Float {
+                abs_epsilon,
+                rel_epsilon,
+            } => write!(f, "float:abs={abs_epsilon:e},rel={rel_epsilon:e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comparator(s: &str) -> Comparator {
+        Comparator::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_exact() {
+        assert!(Comparator::Exact.compare("a b\nc\n", "a b\r\nc"));
+        assert!(!Comparator::Exact.compare("a b", "a  b"));
+    }
+
+    #[test]
+    fn test_tokens_ignore_whitespace() {
+        assert!(comparator("tokens").compare("1 2\n3", "1\n2   3  "));
+        assert!(!comparator("tokens").compare("1 2 3", "1 2"));
+        assert!(!comparator("tokens").compare("1 2", "1 2 3"));
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        assert!(comparator("case-insensitive").compare("YES\nNo", "yes\nNO"));
+        assert!(!comparator("case-insensitive").compare("yes no", "yes  no"));
+    }
+
+    #[test]
+    fn test_unordered_lines() {
+        assert!(comparator("unordered-lines").compare("a\nb\nb", "b\na\nb"));
+        assert!(!comparator("unordered-lines").compare("a\nb\nb", "b\na\na"));
+    }
+
+    #[test]
+    fn test_float_tolerance() {
+        let absolute = comparator("float:abs=0.01");
+        assert!(absolute.compare("1.00 x", "1.005 x"));
+        assert!(!absolute.compare("1.00 x", "1.02 x"));
+        assert!(!absolute.compare("1.00 x", "1.00 y"));
+        let relative = comparator("float:rel=0.01");
+        assert!(relative.compare("1000", "1009"));
+        assert!(!relative.compare("1000", "1011"));
+    }
+
+    #[test]
+    fn test_float_special_values() {
+        let float = comparator("float:1");
+        assert!(float.compare("nan", "nan"));
+        assert!(!float.compare("nan", "1"));
+        assert!(float.compare("inf -inf", "inf -inf"));
+        assert!(!float.compare("inf", "infinity"));
+        assert!(!float.compare("inf", "1e308"));
+        assert!(!float.compare("1", "inf"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Comparator::from_str("fuzzy").is_err());
+        assert!(Comparator::from_str("tokens:1").is_err());
+        assert!(Comparator::from_str("float:x").is_err());
+        assert!(Comparator::from_str("float:-1").is_err());
+        assert!(Comparator::from_str("float:eps=1").is_err());
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        for s in [
+            "exact",
+            "tokens",
+            "case-insensitive",
+            "unordered-lines",
+            "float",
+            "float:abs=0.5,rel=0",
+        ] {
+            let comparator = comparator(s);
+            assert_eq!(Comparator::from_str(&comparator.to_string()).unwrap(), comparator);
+        }
+    }
+
+    #[test]
+    fn test_annotate_case_insensitive() {
+        let annotation = comparator("case-insensitive").annotate("YES\nA B\nno", "yes\nA  b\nyes");
+        let matches: Vec<bool> = annotation.tokens.iter().map(|token| token.matches).collect();
+        assert_eq!(matches, vec![true, false, false, false]);
+        assert_eq!(annotation.tokens[3].range, 9..12);
+    }
+
+    #[test]
+    fn test_annotate_unordered_lines() {
+        let annotation = comparator("unordered-lines").annotate("a\nb\nc\n", "c\nx\na\n");
+        let matches: Vec<bool> = annotation.tokens.iter().map(|token| token.matches).collect();
+        assert_eq!(matches, vec![true, false, true]);
+        assert_eq!(annotation.missing, 1);
+    }
+
+    #[test]
+    fn test_annotate_highlights_only_wrong_tokens() {
+        let annotation = comparator("float:0.1").annotate("1.0 2.0 3.0 4.0", "1.05  2.5 3.0");
+        let matches: Vec<bool> = annotation.tokens.iter().map(|token| token.matches).collect();
+        assert_eq!(matches, vec![true, false, true]);
+        assert_eq!(annotation.tokens[1].range, 6..9);
+        assert_eq!(annotation.missing, 1);
+    }
+}
@@ -0,0 +1,109 @@
+use std::process::ExitStatus;
+
+/// Why a solution command that did not exit successfully stopped running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitReason {
+    /// The process exited with a non-zero exit code.
+    Code(i32),
+    /// The process was terminated by a signal (Unix-like platforms only).
+    Signal(i32),
+}
+
+impl ExitReason {
+    pub(super) fn from_status(status: &ExitStatus) -> Self {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+
+            if let Some(signal) = status.signal() {
+                return ExitReason::Signal(signal)
+            }
+        }
+        // A process that was not terminated by a signal always has an exit
+        // code, stopped processes are never reported by `wait`.
+        ExitReason::Code(status.code().unwrap_or(-1))
+    }
+
+    /// Conventional name and short description of the signal, for example
+    /// `("SIGSEGV", "segmentation fault")`.
+    pub fn signal_name(&self) -> Option<(&'static str, &'static str)> {
+        match self {
+            ExitReason::Code(_) => None,
+            ExitReason::Signal(signal) => signal_name(*signal),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn signal_name(signal: i32) -> Option<(&'static str, &'static str)> {
+    let name = match signal {
+        libc::SIGHUP => ("SIGHUP", "hangup"),
+        libc::SIGINT => ("SIGINT", "interrupted"),
+        libc::SIGQUIT => ("SIGQUIT", "quit"),
+        libc::SIGILL => ("SIGILL", "illegal instruction"),
+        libc::SIGTRAP => ("SIGTRAP", "trace/breakpoint trap"),
+        libc::SIGABRT => ("SIGABRT", "aborted"),
+        libc::SIGBUS => ("SIGBUS", "bus error"),
+        libc::SIGFPE => ("SIGFPE", "arithmetic exception"),
+        libc::SIGKILL => ("SIGKILL", "killed"),
+        libc::SIGSEGV => ("SIGSEGV", "segmentation fault"),
+        libc::SIGPIPE => ("SIGPIPE", "broken pipe"),
+        libc::SIGALRM => ("SIGALRM", "alarm clock"),
+        libc::SIGTERM => ("SIGTERM", "terminated"),
+        libc::SIGXCPU => ("SIGXCPU", "CPU time limit exceeded"),
+        libc::SIGXFSZ => ("SIGXFSZ", "file size limit exceeded"),
+        libc::SIGSYS => ("SIGSYS", "bad system call"),
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[cfg(not(unix))]
+fn signal_name(_signal: i32) -> Option<(&'static str, &'static str)> {
+    None
+}
+
+impl std::fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match (self, self.signal_name()) {
+            (ExitReason::Code(code), _) => write!(f, "exited with code {code}"),
+            (ExitReason::Signal(_), Some((name, description))) => {
+                write!(f, "terminated by signal {name} ({description})")
+            }
+            (ExitReason::Signal(signal), None) => write!(f, "terminated by signal {signal}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_exit_code() {
+        assert_eq!(ExitReason::Code(1).to_string(), "exited with code 1");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_display_signal() {
+        assert_eq!(
+            ExitReason::Signal(libc::SIGSEGV).to_string(),
+            "terminated by signal SIGSEGV (segmentation fault)"
+        );
+        assert_eq!(ExitReason::Signal(1000).to_string(), "terminated by signal 1000");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_from_status() {
+        use std::os::unix::process::ExitStatusExt;
+
+        // Raw wait statuses: exit code in the second byte, signal in the first
+        assert_eq!(ExitReason::from_status(&ExitStatus::from_raw(3 << 8)), ExitReason::Code(3));
+        assert_eq!(
+            ExitReason::from_status(&ExitStatus::from_raw(libc::SIGABRT)),
+            ExitReason::Signal(libc::SIGABRT)
+        );
+    }
+}
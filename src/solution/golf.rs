@@ -0,0 +1,36 @@
+/// Count the length of a solution the way CodinGame does in shortest mode:
+/// the number of characters (not bytes) in the source code. Windows style
+/// line endings count as a single character.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::solution::code_length;
+///
+/// assert_eq!(code_length("print(input())"), 14);
+/// assert_eq!(code_length("puts 'ä'\r\n"), 9);
+/// ```
+pub fn code_length(source: &str) -> usize {
+    source.chars().count() - source.matches("\r\n").count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_length_counts_characters() {
+        assert_eq!(code_length("λ→"), 2);
+        assert_eq!(code_length("🦀"), 1);
+    }
+
+    #[test]
+    fn test_code_length_normalizes_line_endings() {
+        assert_eq!(code_length("a\r\nb\r\n"), code_length("a\nb\n"));
+    }
+
+    #[test]
+    fn test_code_length_of_empty_source() {
+        assert_eq!(code_length(""), 0);
+    }
+}
@@ -0,0 +1,207 @@
+use std::process::Command;
+use std::time::Duration;
+
+use super::ResourceUsage;
+
+/// Restrictions applied to every execution of a solution command.
+///
+/// Memory and CPU time limits are enforced using resource limits (see
+/// `setrlimit(2)`) and are only supported on Unix-like platforms. They are
+/// ignored elsewhere.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    /// Wall clock time after which the solution is killed.
+    pub timeout: Duration,
+    /// Maximum size of the virtual address space of the solution in bytes.
+    pub memory: Option<u64>,
+    /// Maximum amount of CPU time the solution may use. Resource limits only
+    /// have a granularity of one second so this is rounded up to whole
+    /// seconds.
+    pub cpu_time: Option<Duration>,
+    /// Maximum number of bytes the solution may print to STDOUT or STDERR.
+    /// The solution is killed as soon as either one exceeds the limit.
+    pub output: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            timeout: Duration::MAX,
+            memory: None,
+            cpu_time: None,
+            output: None,
+        }
+    }
+}
+
+impl Limits {
+    /// Limits that only restrict the wall clock time of a solution.
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Limits {
+            timeout,
+            ..Default::default()
+        }
+    }
+
+    fn cpu_time_secs(&self) -> Option<u64> {
+        self.cpu_time
+            .map(|cpu_time| (cpu_time.as_secs() + u64::from(cpu_time.subsec_nanos() > 0)).max(1))
+    }
+}
+
+/// Messages that language runtimes print to STDERR before aborting when a
+/// memory allocation fails.
+const OUT_OF_MEMORY_MESSAGES: [&str; 3] = ["memory allocation of", "std::bad_alloc", "out of memory"];
+
+/// Returns true if a failed solution ran out of memory.
+///
+/// The limit applies to the virtual address space, which is always a bit
+/// larger than the resident set, so a solution that ran out of memory by
+/// using it got close to the limit. Solutions that fail to make one huge
+/// allocation don't, but then the runtime usually says so before aborting.
+/// Crashes are not evidence on their own as they are far more often caused
+/// by bugs than by failed allocations.
+#[cfg(unix)]
+pub(super) fn hit_memory_limit(
+    limits: &Limits,
+    status: &std::process::ExitStatus,
+    usage: &ResourceUsage,
+    stderr: &[u8],
+) -> bool {
+    use std::os::unix::process::ExitStatusExt;
+
+    let Some(memory) = limits.memory else { return false };
+    // At least 90% of the limit
+    if usage
+        .max_rss
+        .is_some_and(|max_rss| max_rss.saturating_mul(10) >= memory.saturating_mul(9))
+    {
+        return true
+    }
+    let stderr = String::from_utf8_lossy(stderr);
+    status.signal() == Some(libc::SIGABRT) && OUT_OF_MEMORY_MESSAGES.iter().any(|msg| stderr.contains(msg))
+}
+
+#[cfg(not(unix))]
+pub(super) fn hit_memory_limit(
+    _limits: &Limits,
+    _status: &std::process::ExitStatus,
+    _usage: &ResourceUsage,
+    _stderr: &[u8],
+) -> bool {
+    false
+}
+
+/// Returns true if a failed solution used up its CPU time: either the kernel
+/// sent it SIGXCPU, which it only does for the CPU time limit, or it used at
+/// least as much CPU time as allowed. Being killed with SIGKILL alone is not
+/// evidence because the out-of-memory killer or the user can also do that.
+#[cfg(unix)]
+pub(super) fn hit_cpu_limit(
+    limits: &Limits,
+    status: &std::process::ExitStatus,
+    usage: &ResourceUsage,
+) -> bool {
+    use std::os::unix::process::ExitStatusExt;
+
+    let Some(cpu_time) = limits.cpu_time else {
+        return false
+    };
+    let used = usage.user_time.zip(usage.system_time).map(|(user, system)| user + system);
+    status.signal() == Some(libc::SIGXCPU) || used.is_some_and(|used| used >= cpu_time)
+}
+
+#[cfg(not(unix))]
+pub(super) fn hit_cpu_limit(
+    _limits: &Limits,
+    _status: &std::process::ExitStatus,
+    _usage: &ResourceUsage,
+) -> bool {
+    false
+}
+
+/// Make `command` apply the memory and CPU time limits to the processes it
+/// spawns. Should only be called once per `Command`.
+#[cfg(unix)]
+pub(super) fn apply_resource_limits(command: &mut Command, limits: &Limits) {
+    use std::os::unix::process::CommandExt;
+
+    let memory = limits.memory;
+    let cpu_secs = limits.cpu_time_secs();
+    if memory.is_none() && cpu_secs.is_none() {
+        return
+    }
+
+    // SAFETY: the closure only calls setrlimit, which is async-signal-safe.
+    unsafe {
+        command.pre_exec(move || {
+            if let Some(bytes) = memory {
+                check_os_result(libc::setrlimit(libc::RLIMIT_AS, &rlimit(bytes, bytes)))?;
+            }
+            if let Some(secs) = cpu_secs {
+                // Leave a second between the soft and the hard limit so the
+                // solution gets a SIGXCPU before it gets killed.
+                check_os_result(libc::setrlimit(libc::RLIMIT_CPU, &rlimit(secs, secs + 1)))?;
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+pub(super) fn apply_resource_limits(_command: &mut Command, _limits: &Limits) {}
+
+#[cfg(unix)]
+fn rlimit(soft: u64, hard: u64) -> libc::rlimit {
+    libc::rlimit {
+        rlim_cur: soft as libc::rlim_t,
+        rlim_max: hard as libc::rlim_t,
+    }
+}
+
+#[cfg(unix)]
+fn check_os_result(ret: libc::c_int) -> std::io::Result<()> {
+    match ret {
+        0 => Ok(()),
+        _ => Err(std::io::Error::last_os_error()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_time_is_rounded_up() {
+        let limits = Limits {
+            cpu_time: Some(Duration::from_millis(1500)),
+            ..Default::default()
+        };
+        assert_eq!(limits.cpu_time_secs(), Some(2));
+        let limits = Limits {
+            cpu_time: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+        assert_eq!(limits.cpu_time_secs(), Some(1));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cpu_limit_is_based_on_usage() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let limits = Limits {
+            cpu_time: Some(Duration::from_millis(1500)),
+            ..Default::default()
+        };
+        let usage = |cpu_millis| ResourceUsage {
+            user_time: Some(Duration::from_millis(cpu_millis)),
+            system_time: Some(Duration::from_millis(10)),
+            ..Default::default()
+        };
+        let killed = std::process::ExitStatus::from_raw(libc::SIGKILL);
+        assert!(hit_cpu_limit(&limits, &killed, &usage(2000)));
+        assert!(!hit_cpu_limit(&limits, &killed, &usage(100)));
+        assert!(!hit_cpu_limit(&Limits::default(), &killed, &usage(2000)));
+    }
+}
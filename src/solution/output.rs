@@ -0,0 +1,160 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// Output of a solution command.
+///
+/// Solutions may print anything, so the output is kept as raw bytes instead
+/// of assuming it is valid UTF-8.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::solution::Output;
+///
+/// let output = Output::from(b"caf\xE9 ok".to_vec());
+/// assert_eq!(output.to_str_lossy(), "caf\u{FFFD} ok");
+/// assert_eq!(output.invalid_utf8(), vec![3..4]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Output(Vec<u8>);
+
+/// A piece of an [Output] that is either valid UTF-8 or a sequence of bytes
+/// that is not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputChunk<'a> {
+    Valid(&'a str),
+    Invalid(&'a [u8]),
+}
+
+impl Output {
+    /// Normalize the output for comparison: line endings are converted to
+    /// `\n` and trailing whitespace is removed.
+    pub(super) fn normalized(bytes: &[u8]) -> Self {
+        let mut normalized = Vec::with_capacity(bytes.len());
+        let mut bytes = bytes.iter().peekable();
+        while let Some(&byte) = bytes.next() {
+            if !(byte == b'\r' && bytes.peek() == Some(&&b'\n')) {
+                normalized.push(byte);
+            }
+        }
+        let trimmed_len = match std::str::from_utf8(&normalized) {
+            Ok(text) => text.trim_end().len(),
+            Err(_) => normalized
+                .iter()
+                .rposition(|byte| !byte.is_ascii_whitespace())
+                .map_or(0, |pos| pos + 1),
+        };
+        normalized.truncate(trimmed_len);
+        Output(normalized)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The output as a string, if it is valid UTF-8.
+    pub fn to_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.0).ok()
+    }
+
+    /// The output as a string with invalid UTF-8 sequences replaced by
+    /// U+FFFD REPLACEMENT CHARACTER.
+    pub fn to_str_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.0)
+    }
+
+    /// Split the output into valid UTF-8 and invalid byte sequences.
+    pub fn chunks(&self) -> Vec<OutputChunk<'_>> {
+        let mut chunks = Vec::new();
+        let mut rest = self.0.as_slice();
+        while !rest.is_empty() {
+            let (valid, invalid_len) = match std::str::from_utf8(rest) {
+                Ok(valid) => (valid, 0),
+                Err(err) => {
+                    let (valid, _) = rest.split_at(err.valid_up_to());
+                    let valid = std::str::from_utf8(valid).expect("prefix should be valid UTF-8");
+                    // An incomplete sequence can only be at the very end
+                    (valid, err.error_len().unwrap_or(rest.len() - valid.len()))
+                }
+            };
+            if !valid.is_empty() {
+                chunks.push(OutputChunk::Valid(valid));
+            }
+            let invalid = &rest[valid.len()..valid.len() + invalid_len];
+            if !invalid.is_empty() {
+                chunks.push(OutputChunk::Invalid(invalid));
+            }
+            rest = &rest[valid.len() + invalid_len..];
+        }
+        chunks
+    }
+
+    /// Byte ranges of the invalid UTF-8 sequences in the output.
+    pub fn invalid_utf8(&self) -> Vec<Range<usize>> {
+        let mut offset = 0;
+        let mut ranges = Vec::new();
+        for chunk in self.chunks() {
+            let len = match chunk {
+                OutputChunk::Valid(text) => text.len(),
+                OutputChunk::Invalid(bytes) => {
+                    ranges.push(offset..offset + bytes.len());
+                    bytes.len()
+                }
+            };
+            offset += len;
+        }
+        ranges
+    }
+}
+
+impl From<Vec<u8>> for Output {
+    fn from(bytes: Vec<u8>) -> Self {
+        Output(bytes)
+    }
+}
+
+impl PartialEq<str> for Output {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other.as_bytes()
+    }
+}
+
+impl PartialEq<&str> for Output {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == other.as_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalized() {
+        assert_eq!(Output::normalized(b"a\r\nb\r\n\r\n  "), "a\nb");
+        assert_eq!(Output::normalized(b"a\rb\xFF\r\n").as_bytes(), b"a\rb\xFF");
+    }
+
+    #[test]
+    fn test_valid_output() {
+        let output = Output::from(b"hello".to_vec());
+        assert_eq!(output.to_str(), Some("hello"));
+        assert!(output.invalid_utf8().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_utf8_ranges() {
+        let output = Output::from(b"\xFFok\xE2\x82 done\xC0".to_vec());
+        assert_eq!(output.to_str(), None);
+        assert_eq!(output.invalid_utf8(), vec![0..1, 3..5, 10..11]);
+        assert_eq!(output.to_str_lossy(), "\u{FFFD}ok\u{FFFD} done\u{FFFD}");
+    }
+}
@@ -0,0 +1,266 @@
+use std::process::{Child, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+// How often a running solution is checked for requests to kill it
+const KILL_REQUEST_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Resources used by a single execution of a solution command.
+///
+/// CPU times and peak memory usage are only measured on Unix-like platforms,
+/// elsewhere they are `None`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceUsage {
+    /// Wall clock time from starting the solution until it exited.
+    pub wall_time: Duration,
+    /// CPU time spent in user mode.
+    pub user_time: Option<Duration>,
+    /// CPU time spent in kernel mode.
+    pub system_time: Option<Duration>,
+    /// Peak resident set size in bytes.
+    pub max_rss: Option<u64>,
+}
+
+impl ResourceUsage {
+    /// Add the resources used by `other` to the total. Times are summed and
+    /// the peak memory usage is the highest of the two.
+    pub fn accumulate(&mut self, other: &ResourceUsage) {
+        fn add(a: Option<Duration>, b: Option<Duration>) -> Option<Duration> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a + b),
+                (a, b) => a.or(b),
+            }
+        }
+        self.wall_time += other.wall_time;
+        self.user_time = add(self.user_time, other.user_time);
+        self.system_time = add(self.system_time, other.system_time);
+        self.max_rss = self.max_rss.max(other.max_rss);
+    }
+}
+
+impl std::fmt::Display for ResourceUsage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:.3}s", self.wall_time.as_secs_f64())?;
+        if let Some(user_time) = self.user_time {
+            write!(f, ", user {:.3}s", user_time.as_secs_f64())?;
+        }
+        if let Some(system_time) = self.system_time {
+            write!(f, ", sys {:.3}s", system_time.as_secs_f64())?;
+        }
+        if let Some(max_rss) = self.max_rss {
+            write!(f, ", {:.1} MB", max_rss as f64 / (1024.0 * 1024.0))?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of waiting for a solution process to exit.
+pub(super) struct Exited {
+    pub status: ExitStatus,
+    pub timed_out: bool,
+    pub usage: ResourceUsage,
+}
+
+/// Wait for `child` to exit, killing its process group if it runs longer
+/// than `timeout` or as soon as `kill_requested` is set.
+///
+/// The child must have been started as the leader of its own process group.
+/// Once it has exited, any processes it left behind in its process group are
+/// killed so that they can't keep its output pipes open.
+///
+/// The child is reaped with `wait4` so its resource usage can be collected.
+/// Because of this the standard library does not know that the child has
+/// exited and methods like [Child::wait] must not be called afterwards.
+#[cfg(unix)]
+pub(super) fn wait_with_usage(
+    child: &mut Child,
+    started: Instant,
+    timeout: Duration,
+    kill_requested: &AtomicBool,
+) -> Exited {
+    use std::sync::mpsc;
+
+    let pid = child.id() as libc::pid_t;
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        // The receiver only hangs up if the waiting thread panicked.
+        let _ = sender.send(wait_for_exit(pid));
+    });
+
+    let (waited, timed_out) = loop {
+        let remaining = timeout.saturating_sub(started.elapsed());
+        match receiver.recv_timeout(remaining.min(KILL_REQUEST_POLL_INTERVAL)) {
+            Ok(waited) => break (waited, false),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                let timed_out = started.elapsed() >= timeout;
+                if timed_out || kill_requested.load(Ordering::Relaxed) {
+                    // The child has not been reaped yet so its process group
+                    // still belongs to it.
+                    kill_process_group(child.id());
+                    break (receiver.recv().expect("Waiting thread should send a result"), timed_out)
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => panic!("Waiting thread should send a result"),
+        }
+    };
+    let wall_time = started.elapsed();
+    waited.expect("Process should be able to wait for execution");
+
+    // The child has exited but has not been reaped yet, so its process group
+    // can't have been taken over by another process.
+    kill_process_group(child.id());
+    let (status, rusage) = wait4(pid).expect("Process should be able to wait for execution");
+
+    Exited {
+        status,
+        timed_out,
+        usage: ResourceUsage {
+            wall_time,
+            user_time: Some(timeval_to_duration(rusage.ru_utime)),
+            system_time: Some(timeval_to_duration(rusage.ru_stime)),
+            max_rss: Some(max_rss_bytes(rusage.ru_maxrss)),
+        },
+    }
+}
+
+#[cfg(not(unix))]
+pub(super) fn wait_with_usage(
+    child: &mut Child,
+    started: Instant,
+    timeout: Duration,
+    kill_requested: &AtomicBool,
+) -> Exited {
+    use wait_timeout::ChildExt;
+
+    let timed_out = loop {
+        let remaining = timeout.saturating_sub(started.elapsed());
+        let waited = child
+            .wait_timeout(remaining.min(KILL_REQUEST_POLL_INTERVAL))
+            .expect("Process should be able to wait for execution");
+        if waited.is_some() {
+            break false
+        }
+        let timed_out = started.elapsed() >= timeout;
+        if timed_out || kill_requested.load(Ordering::Relaxed) {
+            // Killing fails if the process has exited in the meantime
+            let _ = child.kill();
+            break timed_out
+        }
+    };
+
+    let status = child.wait().expect("Process should allow waiting for its execution");
+
+    Exited {
+        status,
+        timed_out,
+        usage: ResourceUsage {
+            wall_time: started.elapsed(),
+            ..Default::default()
+        },
+    }
+}
+
+/// Forcefully terminate every process in the process group led by the child
+/// process with the given id. Must only be called for a child process that
+/// has not been reaped yet.
+#[cfg(unix)]
+pub(super) fn kill_process_group(pid: u32) {
+    // SAFETY: sending a signal has no memory safety implications.
+    unsafe { libc::killpg(pid as libc::pid_t, libc::SIGKILL) };
+}
+
+/// Block until the process exits without reaping it.
+#[cfg(unix)]
+fn wait_for_exit(pid: libc::pid_t) -> std::io::Result<()> {
+    // SAFETY: siginfo_t is a plain C struct for which all zeroes is a valid value.
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    loop {
+        // SAFETY: info is a valid pointer for the duration of the call.
+        match unsafe {
+            libc::waitid(libc::P_PID, pid as libc::id_t, &mut info, libc::WEXITED | libc::WNOWAIT)
+        } {
+            -1 => {
+                let error = std::io::Error::last_os_error();
+                if error.kind() != std::io::ErrorKind::Interrupted {
+                    return Err(error)
+                }
+            }
+            _ => return Ok(()),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn wait4(pid: libc::pid_t) -> std::io::Result<(ExitStatus, libc::rusage)> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let mut status = 0;
+    // SAFETY: rusage is a plain C struct for which all zeroes is a valid value.
+    let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+    loop {
+        // SAFETY: status and rusage are valid pointers for the duration of the call.
+        match unsafe { libc::wait4(pid, &mut status, 0, &mut rusage) } {
+            -1 => {
+                let error = std::io::Error::last_os_error();
+                if error.kind() != std::io::ErrorKind::Interrupted {
+                    return Err(error)
+                }
+            }
+            _ => return Ok((ExitStatus::from_raw(status), rusage)),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn timeval_to_duration(tv: libc::timeval) -> Duration {
+    Duration::from_secs(tv.tv_sec as u64) + Duration::from_micros(tv.tv_usec as u64)
+}
+
+// macOS reports the peak resident set size in bytes, other platforms in
+// kilobytes.
+#[cfg(all(unix, target_os = "macos"))]
+fn max_rss_bytes(ru_maxrss: libc::c_long) -> u64 {
+    ru_maxrss as u64
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn max_rss_bytes(ru_maxrss: libc::c_long) -> u64 {
+    ru_maxrss as u64 * 1024
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accumulate_usage() {
+        let mut total = ResourceUsage::default();
+        total.accumulate(&ResourceUsage {
+            wall_time: Duration::from_millis(100),
+            user_time: Some(Duration::from_millis(50)),
+            system_time: Some(Duration::from_millis(10)),
+            max_rss: Some(2048),
+        });
+        total.accumulate(&ResourceUsage {
+            wall_time: Duration::from_millis(200),
+            user_time: Some(Duration::from_millis(150)),
+            system_time: Some(Duration::from_millis(20)),
+            max_rss: Some(1024),
+        });
+        assert_eq!(total.wall_time, Duration::from_millis(300));
+        assert_eq!(total.user_time, Some(Duration::from_millis(200)));
+        assert_eq!(total.system_time, Some(Duration::from_millis(30)));
+        assert_eq!(total.max_rss, Some(2048));
+    }
+
+    #[test]
+    fn test_display_usage() {
+        let usage = ResourceUsage {
+            wall_time: Duration::from_millis(1234),
+            user_time: Some(Duration::from_millis(1000)),
+            system_time: Some(Duration::from_millis(5)),
+            max_rss: Some(3 * 1024 * 1024),
+        };
+        assert_eq!(usage.to_string(), "1.234s, user 1.000s, sys 0.005s, 3.0 MB");
+    }
+}
@@ -1,7 +1,13 @@
+use super::verifier::Verdict;
+use super::{ExitReason, Output, ResourceUsage};
+
 pub enum CommandExit {
     Ok,
-    Error,
+    Error(ExitReason),
     Timeout,
+    MemoryLimitExceeded,
+    CpuLimitExceeded,
+    OutputLimitExceeded,
 }
 
 /// Represents the outcome of running a testcase. [TestResult::Success] means
@@ -12,96 +18,248 @@ pub enum TestResult {
     /// Solution command produced the expected output. A test run is considered
     /// a success even if it runs into a runtime error or times out if its
     /// output was correct (just like it works on CodinGame).
-    Success,
+    Success { usage: ResourceUsage },
     /// Solution command failed to run. This may happen for example if the
     /// executable does not exist or if the current user does not have
     /// permission to execute it.
     UnableToRun { error_msg: String },
     /// Solution command exited normally but did not produce the expected
-    /// output.
-    WrongOutput { stdout: String, stderr: String },
-    /// Solution command encountered a runtime error (exited non-zero).
-    RuntimeError { stdout: String, stderr: String },
+    /// output. `message` is an explanation given by an external checker.
+    WrongOutput {
+        stdout: Output,
+        stderr: Output,
+        message: Option<String>,
+        usage: ResourceUsage,
+    },
+    /// Solution command encountered a runtime error (exited non-zero or was
+    /// terminated by a signal).
+    RuntimeError {
+        stdout: Output,
+        stderr: Output,
+        exit: ExitReason,
+        usage: ResourceUsage,
+    },
     /// Solution command timed out.
-    Timeout { stdout: String, stderr: String },
+    Timeout {
+        stdout: Output,
+        stderr: Output,
+        usage: ResourceUsage,
+    },
+    /// Solution command ran out of memory because it exceeded the memory
+    /// limit.
+    MemoryLimitExceeded {
+        stdout: Output,
+        stderr: Output,
+        usage: ResourceUsage,
+    },
+    /// Solution command was terminated because it exceeded the CPU time
+    /// limit.
+    CpuLimitExceeded {
+        stdout: Output,
+        stderr: Output,
+        usage: ResourceUsage,
+    },
+    /// Solution command was terminated because it printed more than the
+    /// output limit allows. The output is truncated to the limit.
+    OutputLimitExceeded {
+        stdout: Output,
+        stderr: Output,
+        usage: ResourceUsage,
+    },
+    /// The external checker program that verifies the output of the solution
+    /// failed, so it is unknown whether the output was correct.
+    CheckerFailed { error_msg: String, usage: ResourceUsage },
+}
+
+/// Normalize text for comparison: line endings are converted to `\n` and
+/// trailing whitespace is removed.
+pub(super) fn normalize_output(output: &str) -> String {
+    output.replace("\r\n", "\n").trim_end().to_string()
 }
 
 impl TestResult {
-    pub(crate) fn from_output(
-        expected: &str,
+    pub(crate) fn from_verdict(
+        verdict: Verdict,
         stdout: Vec<u8>,
         stderr: Vec<u8>,
         exit_status: CommandExit,
     ) -> Self {
-        let stdout = String::from_utf8(stdout)
-            .unwrap_or_default()
-            .replace("\r\n", "\n")
-            .trim_end()
-            .to_string();
-        let stderr = String::from_utf8(stderr).unwrap_or_default();
+        let stdout = Output::normalized(&stdout);
+        let stderr = Output::from(stderr);
+
+        let usage = ResourceUsage::default();
+
+        let message = match verdict {
+            // The output is incomplete so it can't be correct even if the
+            // truncated part happens to be accepted
+            _ if matches!(exit_status, CommandExit::OutputLimitExceeded) => None,
+            Verdict::Accepted => return TestResult::Success { usage },
+            Verdict::Failed { error_msg } => return TestResult::CheckerFailed { error_msg, usage },
+            Verdict::Rejected { message } => message,
+        };
 
         match exit_status {
-            _ if stdout == expected.trim_end() => TestResult::Success,
-            CommandExit::Timeout => TestResult::Timeout { stdout, stderr },
-            CommandExit::Ok => TestResult::WrongOutput { stdout, stderr },
-            CommandExit::Error => TestResult::RuntimeError { stdout, stderr },
+            CommandExit::OutputLimitExceeded => TestResult::OutputLimitExceeded {
+                stdout,
+                stderr,
+                usage,
+            },
+            CommandExit::Timeout => TestResult::Timeout {
+                stdout,
+                stderr,
+                usage,
+            },
+            CommandExit::Ok => TestResult::WrongOutput {
+                stdout,
+                stderr,
+                message,
+                usage,
+            },
+            CommandExit::Error(exit) => TestResult::RuntimeError {
+                stdout,
+                stderr,
+                exit,
+                usage,
+            },
+            CommandExit::MemoryLimitExceeded => TestResult::MemoryLimitExceeded {
+                stdout,
+                stderr,
+                usage,
+            },
+            CommandExit::CpuLimitExceeded => TestResult::CpuLimitExceeded {
+                stdout,
+                stderr,
+                usage,
+            },
+        }
+    }
+
+    /// Record the resources the solution command used.
+    pub(crate) fn with_usage(mut self, new_usage: ResourceUsage) -> Self {
+        match &mut self {
+            TestResult::UnableToRun { .. } => {}
+            TestResult::Success { usage }
+            | TestResult::CheckerFailed { usage, .. }
+            | TestResult::WrongOutput { usage, .. }
+            | TestResult::RuntimeError { usage, .. }
+            | TestResult::Timeout { usage, .. }
+            | TestResult::MemoryLimitExceeded { usage, .. }
+            | TestResult::CpuLimitExceeded { usage, .. }
+            | TestResult::OutputLimitExceeded { usage, .. } => *usage = new_usage,
+        }
+        self
+    }
+
+    /// Resources used by the solution command, or `None` if it could not be
+    /// run at all.
+    pub fn usage(&self) -> Option<&ResourceUsage> {
+        match self {
+            TestResult::UnableToRun { .. } => None,
+            TestResult::Success { usage }
+            | TestResult::CheckerFailed { usage, .. }
+            | TestResult::WrongOutput { usage, .. }
+            | TestResult::RuntimeError { usage, .. }
+            | TestResult::Timeout { usage, .. }
+            | TestResult::MemoryLimitExceeded { usage, .. }
+            | TestResult::CpuLimitExceeded { usage, .. }
+            | TestResult::OutputLimitExceeded { usage, .. } => Some(usage),
+        }
+    }
+
+    /// What the solution command printed to STDOUT, or `None` if it could not
+    /// be run or passed the testcase.
+    pub fn stdout(&self) -> Option<&Output> {
+        match self {
+            TestResult::Success { .. }
+            | TestResult::UnableToRun { .. }
+            | TestResult::CheckerFailed { .. } => None,
+            TestResult::WrongOutput { stdout, .. }
+            | TestResult::RuntimeError { stdout, .. }
+            | TestResult::Timeout { stdout, .. }
+            | TestResult::MemoryLimitExceeded { stdout, .. }
+            | TestResult::CpuLimitExceeded { stdout, .. }
+            | TestResult::OutputLimitExceeded { stdout, .. } => Some(stdout),
+        }
+    }
+
+    /// What the solution command printed to STDERR, or `None` if it could not
+    /// be run or passed the testcase.
+    pub fn stderr(&self) -> Option<&Output> {
+        match self {
+            TestResult::Success { .. }
+            | TestResult::UnableToRun { .. }
+            | TestResult::CheckerFailed { .. } => None,
+            TestResult::WrongOutput { stderr, .. }
+            | TestResult::RuntimeError { stderr, .. }
+            | TestResult::Timeout { stderr, .. }
+            | TestResult::MemoryLimitExceeded { stderr, .. }
+            | TestResult::CpuLimitExceeded { stderr, .. }
+            | TestResult::OutputLimitExceeded { stderr, .. } => Some(stderr),
         }
     }
 
     /// Returns true if the testcase passed. A testcase passes if the output
     /// of the solution command matches the expected output.
     pub fn is_success(&self) -> bool {
-        matches!(self, TestResult::Success)
+        matches!(self, TestResult::Success { .. })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::solution::verifier::compare_verdict;
+    use crate::solution::Comparator;
+
+    // The result of an output compared exactly to `expected`
+    fn from_output(expected: &str, stdout: Vec<u8>, stderr: Vec<u8>, exit_status: CommandExit) -> TestResult {
+        let verdict = compare_verdict(&Comparator::Exact, expected, &stdout);
+        TestResult::from_verdict(verdict, stdout, stderr, exit_status)
+    }
 
     #[test]
     fn test_testresult_success() {
-        let result = TestResult::from_output("123", "123".into(), vec![], CommandExit::Ok);
-        assert!(matches!(result, TestResult::Success));
+        let result = from_output("123", "123".into(), vec![], CommandExit::Ok);
+        assert!(matches!(result, TestResult::Success { .. }));
     }
 
     #[test]
     fn test_testresult_success_with_trailing_whitespace() {
-        let result = TestResult::from_output("abc\n", "abc".into(), vec![], CommandExit::Ok);
-        assert!(matches!(result, TestResult::Success));
-        let result = TestResult::from_output("abc", "abc\r\n".into(), vec![], CommandExit::Ok);
-        assert!(matches!(result, TestResult::Success));
+        let result = from_output("abc\n", "abc".into(), vec![], CommandExit::Ok);
+        assert!(matches!(result, TestResult::Success { .. }));
+        let result = from_output("abc", "abc\r\n".into(), vec![], CommandExit::Ok);
+        assert!(matches!(result, TestResult::Success { .. }));
     }
 
     #[test]
     fn test_testresult_success_normalized_line_endings() {
-        let result = TestResult::from_output("a\nb\nc", "a\r\nb\r\nc".into(), vec![], CommandExit::Ok);
-        assert!(matches!(result, TestResult::Success));
+        let result = from_output("a\nb\nc", "a\r\nb\r\nc".into(), vec![], CommandExit::Ok);
+        assert!(matches!(result, TestResult::Success { .. }));
     }
 
     #[test]
     fn test_testresult_success_on_timeout() {
-        let result = TestResult::from_output("123", "123".into(), vec![], CommandExit::Timeout);
+        let result = from_output("123", "123".into(), vec![], CommandExit::Timeout);
         assert!(
-            matches!(result, TestResult::Success),
+            matches!(result, TestResult::Success { .. }),
             "TestResult should be `Success` when stdout is correct even if execution timed out"
         )
     }
 
     #[test]
     fn test_testresult_success_on_runtime_error() {
-        let result = TestResult::from_output("123", "123".into(), vec![], CommandExit::Error);
+        let result = from_output("123", "123".into(), vec![], CommandExit::Error(ExitReason::Code(1)));
         assert!(
-            matches!(result, TestResult::Success),
+            matches!(result, TestResult::Success { .. }),
             "TestResult should be `Success` when stdout is correct even if a runtime error occurred"
         )
     }
 
     #[test]
     fn test_testresult_wrong_output() {
-        let result = TestResult::from_output("x\ny\nz", "yyy".into(), "zzz".into(), CommandExit::Ok);
+        let result = from_output("x\ny\nz", "yyy".into(), "zzz".into(), CommandExit::Ok);
         match result {
-            TestResult::WrongOutput { stdout, stderr } => {
+            TestResult::WrongOutput { stdout, stderr, .. } => {
                 assert_eq!(stdout, "yyy");
                 assert_eq!(stderr, "zzz");
             }
@@ -111,9 +269,9 @@ mod tests {
 
     #[test]
     fn test_testresult_timed_out() {
-        let result = TestResult::from_output("xxx", "yyy".into(), "zzz".into(), CommandExit::Timeout);
+        let result = from_output("xxx", "yyy".into(), "zzz".into(), CommandExit::Timeout);
         match result {
-            TestResult::Timeout { stdout, stderr } => {
+            TestResult::Timeout { stdout, stderr, .. } => {
                 assert_eq!(stdout, "yyy");
                 assert_eq!(stderr, "zzz");
             }
@@ -123,13 +281,59 @@ mod tests {
 
     #[test]
     fn test_testresult_runtime_error() {
-        let result = TestResult::from_output("xxx", "yyy".into(), "zzz".into(), CommandExit::Error);
+        let result = from_output("xxx", "yyy".into(), "zzz".into(), CommandExit::Error(ExitReason::Code(1)));
         match result {
-            TestResult::RuntimeError { stdout, stderr } => {
+            TestResult::RuntimeError {
+                stdout, stderr, exit, ..
+            } => {
                 assert_eq!(stdout, "yyy");
                 assert_eq!(stderr, "zzz");
+                assert_eq!(exit, ExitReason::Code(1));
             }
             other => panic!("expected TestResult::RuntimeError but found {:?}", other),
         }
     }
+
+    #[test]
+    fn test_testresult_memory_limit_exceeded() {
+        let result = from_output("xxx", "yyy".into(), "zzz".into(), CommandExit::MemoryLimitExceeded);
+        assert!(matches!(result, TestResult::MemoryLimitExceeded { .. }));
+    }
+
+    #[test]
+    fn test_testresult_cpu_limit_exceeded() {
+        let result = from_output("xxx", "yyy".into(), "zzz".into(), CommandExit::CpuLimitExceeded);
+        assert!(matches!(result, TestResult::CpuLimitExceeded { .. }));
+    }
+
+    #[test]
+    fn test_testresult_output_limit_exceeded() {
+        let result = from_output("123", "123".into(), vec![], CommandExit::OutputLimitExceeded);
+        assert!(
+            matches!(result, TestResult::OutputLimitExceeded { .. }),
+            "TestResult should not be `Success` when the output was truncated"
+        )
+    }
+
+    #[test]
+    fn test_testresult_rejected_with_message() {
+        let verdict = Verdict::Rejected {
+            message: Some(String::from("too short")),
+        };
+        let result = TestResult::from_verdict(verdict, "abc".into(), vec![], CommandExit::Ok);
+        match result {
+            TestResult::WrongOutput { message, .. } => assert_eq!(message.as_deref(), Some("too short")),
+            other => panic!("expected TestResult::WrongOutput but found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_testresult_checker_failed() {
+        let verdict = Verdict::Failed {
+            error_msg: String::from("checker crashed"),
+        };
+        let result = TestResult::from_verdict(verdict, "abc".into(), vec![], CommandExit::Ok);
+        assert!(matches!(result, TestResult::CheckerFailed { .. }));
+        assert!(!result.is_success());
+    }
 }
@@ -0,0 +1,284 @@
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+
+use super::resource_usage::wait_with_usage;
+use super::{read_output, Comparator, Output};
+use crate::clash::Testcase;
+
+/// Decides whether the output of a solution is correct.
+#[derive(Debug, Clone)]
+pub enum Verifier {
+    /// The output is compared to the expected output of the testcase using
+    /// one of the built-in comparators.
+    Compare(Comparator),
+    /// An external checker program decides whether the output is correct.
+    Checker(Checker),
+}
+
+/// The decision of a [Verifier] about the output of a solution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Verdict {
+    Accepted,
+    Rejected {
+        message: Option<String>,
+    },
+    /// The verifier itself failed, for example because the checker program
+    /// crashed.
+    Failed {
+        error_msg: String,
+    },
+}
+
+impl Default for Verifier {
+    fn default() -> Self {
+        Verifier::Compare(Comparator::Exact)
+    }
+}
+
+impl Verifier {
+    pub(crate) fn verify(&self, testcase: &Testcase, stdout: &[u8]) -> Verdict {
+        match self {
+            Verifier::Compare(comparator) => compare_verdict(comparator, &testcase.test_out, stdout),
+            Verifier::Checker(checker) => checker.check(testcase, stdout),
+        }
+    }
+
+    /// The comparator used for highlighting differences between the expected
+    /// and the actual output. External checkers get exact diffs.
+    pub fn comparator(&self) -> Comparator {
+        match self {
+            Verifier::Compare(comparator) => *comparator,
+            Verifier::Checker(_) => Comparator::Exact,
+        }
+    }
+}
+
+pub(super) fn compare_verdict(comparator: &Comparator, expected: &str, stdout: &[u8]) -> Verdict {
+    // Output that isn't valid UTF-8 can never match the expected output
+    let Some(stdout) = Output::normalized(stdout).to_str().map(str::to_string) else {
+        return Verdict::Rejected { message: None }
+    };
+    if comparator.compare(expected, &stdout) {
+        Verdict::Accepted
+    } else {
+        Verdict::Rejected { message: None }
+    }
+}
+
+/// An external program that verifies the output of a solution.
+///
+/// The checker is executed with three file paths as additional arguments:
+/// the testcase input, the expected output and the actual output of the
+/// solution (in that order). Exit code 0 means the output was accepted and
+/// exit code 1 means it was rejected. Anything the checker prints to STDOUT is
+/// shown as an explanation in the failure report. Any other exit code is
+/// considered a failure of the checker itself.
+#[derive(Debug, Clone)]
+pub struct Checker {
+    program: String,
+    args: Vec<String>,
+    timeout: Duration,
+}
+
+impl Checker {
+    /// How long a checker may run before it is considered to have failed.
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    pub fn new(program: impl Into<String>, args: Vec<String>) -> Self {
+        Checker {
+            program: program.into(),
+            args,
+            timeout: Self::DEFAULT_TIMEOUT,
+        }
+    }
+
+    fn check(&self, testcase: &Testcase, stdout: &[u8]) -> Verdict {
+        let dir = match CheckerFiles::create(testcase, stdout) {
+            Ok(dir) => dir,
+            Err(err) => {
+                return Verdict::Failed {
+                    error_msg: format!("Unable to create files for the checker: {err}"),
+                }
+            }
+        };
+
+        let mut command = Command::new(&self.program);
+        // Like solutions, the checker leads its own process group so that
+        // processes it starts are killed along with it on a timeout.
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        let started = Instant::now();
+        let spawned = command
+            .args(&self.args)
+            .args([dir.input(), dir.expected(), dir.actual()])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut checker = match spawned {
+            Ok(checker) => checker,
+            Err(err) => {
+                return Verdict::Failed {
+                    error_msg: format!("{}: {}", self.program, err),
+                }
+            }
+        };
+
+        // The outputs are drained while waiting so that a checker that
+        // prints a lot can't fill up a pipe and deadlock.
+        let stdout_pipe = checker.stdout.take().expect("STDOUT of checker should be captured");
+        let stderr_pipe = checker.stderr.take().expect("STDERR of checker should be captured");
+        let never_exceeded = AtomicBool::new(false);
+        let (exited, stdout, stderr) = std::thread::scope(|scope| {
+            let stdout_reader = scope.spawn(|| read_output(stdout_pipe, None, &never_exceeded));
+            let stderr_reader = scope.spawn(|| read_output(stderr_pipe, None, &never_exceeded));
+            let exited = wait_with_usage(&mut checker, started, self.timeout, &never_exceeded);
+            let stdout = stdout_reader.join().expect("STDOUT reader thread should not panic");
+            let stderr = stderr_reader.join().expect("STDERR reader thread should not panic");
+            (exited, stdout, stderr)
+        });
+
+        let message = String::from_utf8_lossy(&stdout).trim().to_string();
+        match exited.status.code() {
+            _ if exited.timed_out => Verdict::Failed {
+                error_msg: format!("Checker timed out after {} seconds", self.timeout.as_secs_f64()),
+            },
+            Some(0) => Verdict::Accepted,
+            Some(1) => Verdict::Rejected {
+                message: Some(message).filter(|msg| !msg.is_empty()),
+            },
+            _ => Verdict::Failed {
+                error_msg: format!(
+                    "Checker exited with {}\n{}",
+                    exited.status,
+                    String::from_utf8_lossy(&stderr).trim_end()
+                ),
+            },
+        }
+    }
+}
+
+/// Temporary directory holding the files passed to a [Checker]. The directory
+/// is removed when this is dropped.
+struct CheckerFiles {
+    dir: PathBuf,
+}
+
+impl CheckerFiles {
+    fn create(testcase: &Testcase, stdout: &[u8]) -> std::io::Result<Self> {
+        // A new directory with an unguessable name that only the user can
+        // access, so other users can't read or replace the files.
+        let files = loop {
+            let dir = std::env::temp_dir().join(format!("coctus-checker-{:016x}", rand::random::<u64>()));
+            let mut builder = std::fs::DirBuilder::new();
+            #[cfg(unix)]
+            std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+            match builder.create(&dir) {
+                Ok(()) => break CheckerFiles { dir },
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
+            }
+        };
+        std::fs::write(files.input(), &testcase.test_in)?;
+        std::fs::write(files.expected(), &testcase.test_out)?;
+        std::fs::write(files.actual(), stdout)?;
+        Ok(files)
+    }
+
+    fn input(&self) -> PathBuf {
+        self.dir.join("input.txt")
+    }
+
+    fn expected(&self) -> PathBuf {
+        self.dir.join("expected.txt")
+    }
+
+    fn actual(&self) -> PathBuf {
+        self.dir.join("actual.txt")
+    }
+}
+
+impl Drop for CheckerFiles {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testcase() -> Testcase {
+        Testcase {
+            index: 1,
+            title: String::from("Any order"),
+            test_in: String::from("1 2"),
+            test_out: String::from("1 2"),
+            is_validator: false,
+        }
+    }
+
+    fn sh_checker(script: &str) -> Checker {
+        // `sh -c SCRIPT NAME ARGS...` makes the file paths available as $1 $2 $3
+        Checker::new("sh", vec![String::from("-c"), String::from(script), String::from("checker")])
+    }
+
+    #[test]
+    fn test_checker_receives_files() {
+        let checker = sh_checker(
+            r#"test "$(cat "$1")" = "1 2" && test "$(cat "$2")" = "1 2" && test "$(cat "$3")" = "2 1""#,
+        );
+        assert_eq!(checker.check(&testcase(), b"2 1"), Verdict::Accepted);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_checker_files_are_private() {
+        let checker = sh_checker(r#"test "$(ls -ld "$(dirname "$1")" | cut -c 1-10)" = drwx------"#);
+        assert_eq!(checker.check(&testcase(), b"1 2"), Verdict::Accepted);
+    }
+
+    #[test]
+    fn test_checker_rejects_with_message() {
+        let checker = sh_checker("echo 'expected 2 numbers'; exit 1");
+        let verdict = checker.check(&testcase(), b"1");
+        assert_eq!(
+            verdict,
+            Verdict::Rejected {
+                message: Some(String::from("expected 2 numbers"))
+            }
+        );
+    }
+
+    #[test]
+    fn test_checker_with_large_output() {
+        // More than fits in a pipe buffer
+        let checker = sh_checker("yes | head -c 1000000; yes | head -c 1000000 >&2; exit 1");
+        let Verdict::Rejected { message } = checker.check(&testcase(), b"1") else {
+            panic!("checker should reject the output")
+        };
+        assert_eq!(message.unwrap().len(), 999999);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_checker_timeout_kills_child_processes() {
+        // The sleeping grandchild inherits the STDOUT of the checker
+        let mut checker = sh_checker("sleep 30 & wait");
+        checker.timeout = Duration::from_millis(500);
+        let start = Instant::now();
+        let verdict = checker.check(&testcase(), b"1 2");
+        assert!(matches!(verdict, Verdict::Failed { .. }), "{:?}", verdict);
+        assert!(start.elapsed() < Duration::from_secs(5), "child of the checker should be killed");
+    }
+
+    #[test]
+    fn test_checker_failure() {
+        let checker = sh_checker("exit 3");
+        assert!(matches!(checker.check(&testcase(), b"1 2"), Verdict::Failed { .. }));
+        let checker = Checker::new("this-checker-does-not-exist", vec![]);
+        assert!(matches!(checker.check(&testcase(), b"1 2"), Verdict::Failed { .. }));
+    }
+}
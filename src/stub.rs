@@ -1,3 +1,5 @@
+mod input_checker;
+mod input_generator;
 mod language;
 mod parser;
 mod preprocessor;
@@ -6,6 +8,8 @@ mod stub_config;
 
 use anyhow::Result;
 use indoc::indoc;
+pub use input_checker::{InputChecker, Mismatch};
+pub use input_generator::{GeneratedInput, InputGenerator, ValueRange};
 use language::Language;
 use preprocessor::Renderable;
 use serde::Serialize;
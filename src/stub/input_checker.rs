@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use super::parser::parse_generator_stub;
+use super::{Cmd, Stub, VarType, VariableCommand};
+use crate::clash::{CharClass, VariableConstraints};
+
+/// Checks that inputs have the shape described by a stub generator: the
+/// right number of lines and values on each line, values of the declared
+/// types and words and strings no longer than their maximum length.
+/// Optionally the inputs are also checked against the bounds extracted from
+/// the constraints of a clash.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::stub::InputChecker;
+///
+/// let checker = InputChecker::new("read n:int\nloop n read x:int y:word(3)").unwrap();
+/// assert!(checker.check("2\n1 abc\n2 def").is_empty());
+/// let mismatches = checker.check("2\n1 abcd");
+/// assert_eq!(mismatches[0].to_string(), "line 2: y is 4 characters long, longer than its maximum length 3");
+/// assert_eq!(mismatches[1].to_string(), "line 3: missing line, expected x y");
+/// ```
+#[derive(Debug, Clone)]
+pub struct InputChecker {
+    stub: Stub,
+    constraints: Vec<VariableConstraints>,
+}
+
+/// A way in which an input doesn't match the stub generator or the
+/// constraints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The number of the line, starting from 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl InputChecker {
+    pub fn new(generator: &str) -> Result<Self> {
+        Ok(InputChecker {
+            stub: parse_generator_stub(generator)?,
+            constraints: Vec::new(),
+        })
+    }
+
+    /// Also check the values against the bounds extracted from the
+    /// constraints of a clash (see [crate::clash::parse_constraints]).
+    pub fn apply_constraints(&mut self, constraints: &[VariableConstraints]) {
+        self.constraints = constraints.to_vec();
+    }
+
+    /// All the ways `input` doesn't match. Checking stops early when the
+    /// rest of the input can't be checked, for example when a loop count is
+    /// not a number.
+    pub fn check(&self, input: &str) -> Vec<Mismatch> {
+        let mut lines: Vec<&str> = input.lines().collect();
+        while lines.last().is_some_and(|line| line.trim().is_empty()) {
+            lines.pop();
+        }
+        let mut check = Check {
+            checker: self,
+            lines: &lines,
+            next_line: 0,
+            values: HashMap::new(),
+            mismatches: Vec::new(),
+        };
+        let completed = self.stub.commands.iter().all(|cmd| check.run(cmd).is_some());
+        if completed && check.next_line < lines.len() {
+            let extra = lines.len() - check.next_line;
+            check.mismatch(
+                check.next_line,
+                format!("{extra} unexpected extra line{}", if extra == 1 { "" } else { "s" }),
+            );
+        }
+        check.mismatches
+    }
+}
+
+struct Check<'a> {
+    checker: &'a InputChecker,
+    lines: &'a [&'a str],
+    // Index of the next line to read
+    next_line: usize,
+    // Integer values that have been read so far, for loop counts and lengths
+    values: HashMap<String, i64>,
+    mismatches: Vec<Mismatch>,
+}
+
+impl Check<'_> {
+    // None when the rest of the input can't be checked
+    fn run(&mut self, cmd: &Cmd) -> Option<()> {
+        match cmd {
+            Cmd::Read(variables) => {
+                let line = self.line(variables)?;
+                self.check_line(line, variables, variables.len());
+            }
+            Cmd::Loop { count_var, command } => {
+                let count = self.count(count_var)?;
+                // Iterations that read a line run out of lines after the
+                // remaining ones and stop at the missing line, those that
+                // don't read anything change nothing. Either way there's no
+                // need to go past that, however large the count is.
+                let remaining = self.lines.len() - self.next_line;
+                let iterations =
+                    usize::try_from(count).map_or(remaining + 1, |count| count.min(remaining + 1));
+                for _ in 0..iterations {
+                    let line = self.next_line;
+                    self.run(command)?;
+                    if self.next_line == line {
+                        break
+                    }
+                }
+            }
+            Cmd::LoopLine { count_var, variables } => {
+                let count = self.count(count_var)?;
+                let Some(expected) =
+                    usize::try_from(count).ok().and_then(|count| count.checked_mul(variables.len()))
+                else {
+                    let line = self.next_line.saturating_sub(1);
+                    self.mismatch(line, format!("{count_var} = {count} can't be used as a count"));
+                    return None
+                };
+                let line = self.line(variables)?;
+                self.check_line(line, variables, expected);
+            }
+            Cmd::Write { .. } | Cmd::WriteJoin { .. } | Cmd::External(_) => {}
+        }
+        Some(())
+    }
+
+    fn line(&mut self, variables: &[VariableCommand]) -> Option<usize> {
+        let line = self.next_line;
+        if line >= self.lines.len() {
+            let names: Vec<&str> = variables.iter().map(|var| var.ident.as_str()).collect();
+            self.mismatch(line, format!("missing line, expected {}", names.join(" ")));
+            return None
+        }
+        self.next_line += 1;
+        Some(line)
+    }
+
+    // `variables` repeated to make up `expected` values
+    fn check_line(&mut self, line: usize, variables: &[VariableCommand], expected: usize) {
+        let text = self.lines[line];
+        // A string at the end of a line takes the rest of the line
+        let ends_with_string = variables.last().is_some_and(|var| var.var_type == VarType::String);
+        let tokens: Vec<&str> = if ends_with_string && expected == variables.len() {
+            text.splitn(expected, ' ').collect()
+        } else {
+            text.split_whitespace().collect()
+        };
+        if tokens.len() != expected {
+            let values = |n: usize| format!("{n} value{}", if n == 1 { "" } else { "s" });
+            self.mismatch(line, format!("expected {}, found {}", values(expected), values(tokens.len())));
+        }
+        for (var, token) in variables.iter().cycle().zip(tokens).take(expected) {
+            self.check_value(line, var, token);
+        }
+    }
+
+    fn check_value(&mut self, line: usize, var: &VariableCommand, token: &str) {
+        let name = &var.ident;
+        let constraints = self.checker.constraints.iter().find(|constraint| constraint.variable == *name);
+        let number = match var.var_type {
+            VarType::Int => match token.parse::<i64>() {
+                Ok(value) if i32::try_from(value).is_err() => {
+                    return self.mismatch(line, format!("{name} = {value} does not fit in a 32-bit int"))
+                }
+                Ok(value) => Some(value as f64),
+                Err(_) => {
+                    return self.mismatch(line, format!("expected an integer for {name}, found '{token}'"))
+                }
+            },
+            VarType::Long => match token.parse::<i64>() {
+                Ok(value) => Some(value as f64),
+                Err(_) => {
+                    return self.mismatch(line, format!("expected an integer for {name}, found '{token}'"))
+                }
+            },
+            VarType::Bool => match token {
+                "0" | "false" => Some(0.0),
+                "1" | "true" => Some(1.0),
+                _ => return self.mismatch(line, format!("expected a boolean for {name}, found '{token}'")),
+            },
+            VarType::Float => match token.parse::<f64>() {
+                Ok(value) if value.is_finite() => Some(value),
+                _ => return self.mismatch(line, format!("expected a number for {name}, found '{token}'")),
+            },
+            VarType::Word | VarType::String => None,
+        };
+
+        if let Some(number) = number {
+            if matches!(var.var_type, VarType::Int | VarType::Long | VarType::Bool) {
+                self.values.insert(name.clone(), number as i64);
+            }
+            if let Some(bounds) = constraints.map(|constraint| constraint.value) {
+                if !bounds.contains(number) {
+                    self.mismatch(line, format!("{name} = {token} is outside the constraint {bounds}"));
+                }
+            }
+            return
+        }
+
+        let length = token.chars().count();
+        if let Some(max_length) = var.max_length.as_deref().and_then(|max_length| self.value_of(max_length)) {
+            if length as i64 > max_length {
+                self.mismatch(
+                    line,
+                    format!(
+                        "{name} is {length} characters long, longer than its maximum length {max_length}"
+                    ),
+                );
+            }
+        }
+        let Some(constraints) = constraints else { return };
+        if !constraints.length.contains(length as f64) {
+            let bounds = constraints.length;
+            self.mismatch(line, format!("length of {name} is {length}, outside the constraint {bounds}"));
+        }
+        if !constraints.charset.is_empty() {
+            let allowed: Vec<char> = constraints.charset.iter().flat_map(CharClass::chars).collect();
+            if let Some(ch) = token.chars().find(|ch| !allowed.contains(ch)) {
+                let charset: Vec<String> = constraints.charset.iter().map(ToString::to_string).collect();
+                self.mismatch(line, format!("{name} contains {ch:?}, which is not {}", charset.join(" or ")));
+            }
+        }
+    }
+
+    // Loop counts are either numbers or variables read earlier
+    fn count(&mut self, count: &str) -> Option<i64> {
+        let Some(value) = self.value_of(count) else {
+            let line = self.next_line.min(self.lines.len().saturating_sub(1));
+            self.mismatch(line, format!("can't check the rest of the input without a valid {count}"));
+            return None
+        };
+        if value < 0 {
+            let line = self.next_line.saturating_sub(1);
+            self.mismatch(line, format!("{count} = {value} can't be used as a count"));
+            return None
+        }
+        Some(value)
+    }
+
+    fn value_of(&self, count: &str) -> Option<i64> {
+        count.parse().ok().or_else(|| self.values.get(count).copied())
+    }
+
+    fn mismatch(&mut self, line: usize, message: String) {
+        self.mismatches.push(Mismatch {
+            line: line + 1,
+            message,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(checker: &InputChecker, input: &str) -> Vec<String> {
+        checker.check(input).iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_types_and_counts() {
+        let checker =
+            InputChecker::new("read n:int f:float\nloop n read b:bool big:long\nloopline n x:int").unwrap();
+        assert!(messages(&checker, "2 1.5\n1 10000000000\n0 -3\n4 5\n").is_empty());
+        assert_eq!(
+            messages(&checker, "2 abc\n1 2 3\nyes 4\n4 5 6\n7"),
+            [
+                "line 1: expected a number for f, found 'abc'",
+                "line 2: expected 2 values, found 3 values",
+                "line 3: expected a boolean for b, found 'yes'",
+                "line 4: expected 2 values, found 3 values",
+                "line 5: 1 unexpected extra line",
+            ]
+        );
+        assert_eq!(
+            messages(&checker, "3000000000 1"),
+            [
+                "line 1: n = 3000000000 does not fit in a 32-bit int",
+                "line 1: can't check the rest of the input without a valid n",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_huge_counts() {
+        let checker = InputChecker::new("read n:long\nloopline n a:int b:int c:int").unwrap();
+        assert_eq!(
+            messages(&checker, "7000000000000000000\n1 2 3"),
+            ["line 1: n = 7000000000000000000 can't be used as a count"]
+        );
+        let checker = InputChecker::new("read n:long\nloop n read x:int").unwrap();
+        assert_eq!(messages(&checker, "9000000000000000000\n1\n2"), ["line 4: missing line, expected x"]);
+        let checker = InputChecker::new("read n:long\nloop n write answer").unwrap();
+        assert!(messages(&checker, "9000000000000000000").is_empty());
+    }
+
+    #[test]
+    fn test_words_and_strings() {
+        let checker = InputChecker::new("read len:int\nread w:word(len) s:string(10)").unwrap();
+        assert!(messages(&checker, "3\nabc two words").is_empty());
+        assert_eq!(
+            messages(&checker, "3\nabcd this is too long"),
+            [
+                "line 2: w is 4 characters long, longer than its maximum length 3",
+                "line 2: s is 16 characters long, longer than its maximum length 10",
+            ]
+        );
+        assert_eq!(messages(&checker, "3"), ["line 2: missing line, expected w s"]);
+    }
+
+    #[test]
+    fn test_constraints() {
+        let mut checker = InputChecker::new("read n:int\nloop n read name:word(20)").unwrap();
+        let constraints = crate::clash::parse_constraints(
+            "1 ≤ [[n]] ≤ 2\n1 ≤ length of [[name]] ≤ 5\n[[name]] has only lowercase letters",
+        );
+        checker.apply_constraints(&constraints);
+        assert!(messages(&checker, "2\nab\ncde").is_empty());
+        assert_eq!(
+            messages(&checker, "3\nabcdef\nAb\nc"),
+            [
+                "line 1: n = 3 is outside the constraint [1, 2]",
+                "line 2: length of name is 6, outside the constraint [1, 5]",
+                "line 3: name contains 'A', which is not lowercase",
+            ]
+        );
+    }
+}
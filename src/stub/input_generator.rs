@@ -0,0 +1,669 @@
+use std::collections::{BTreeSet, HashMap};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use rand::Rng;
+
+use super::parser::parse_generator_stub;
+use super::{Cmd, Stub, VarType, VariableCommand};
+use crate::clash::{Bound, CharClass, VariableConstraints};
+
+/// Generates random inputs that have the shape described by a stub generator.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::stub::{InputGenerator, ValueRange};
+/// use rand::SeedableRng;
+///
+/// let mut generator = InputGenerator::new("read n:int\nloop n read x:int y:word(5)").unwrap();
+/// generator.set_range("n", ValueRange::new(3.0, 3.0)).unwrap();
+/// let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+/// let input = generator.generate(&mut rng).unwrap();
+/// assert_eq!(input.lines().count(), 4);
+/// assert_eq!(input.lines().next(), Some("3"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct InputGenerator {
+    stub: Stub,
+    ranges: HashMap<String, ValueRange>,
+    // Characters of words and strings, lowercase letters if missing
+    charsets: HashMap<String, Vec<CharClass>>,
+    // Variables that are used as loop counts or lengths of other variables
+    counts: BTreeSet<String>,
+}
+
+/// An input together with the random choices it was generated from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratedInput {
+    pub input: String,
+    choices: Vec<Choice>,
+}
+
+impl GeneratedInput {
+    // Shorter inputs are smaller, inputs of the same length are compared
+    // alphabetically so that shrinking always ends
+    fn is_smaller_than(&self, other: &GeneratedInput) -> bool {
+        (self.input.len(), &self.input) < (other.input.len(), &other.input)
+    }
+}
+
+/// Inclusive range of the values of a variable in generated inputs. For
+/// words and strings it is the range of their length.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ValueRange {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl ValueRange {
+    pub fn new(min: f64, max: f64) -> Self {
+        ValueRange { min, max }
+    }
+
+    // Random values can only be picked from finite ranges
+    fn is_valid(&self) -> bool {
+        self.min <= self.max && (self.max - self.min).is_finite()
+    }
+}
+
+impl InputGenerator {
+    pub fn new(generator: &str) -> Result<Self> {
+        let stub = parse_generator_stub(generator)?;
+        let mut counts = BTreeSet::new();
+        for cmd in &stub.commands {
+            collect_counts(cmd, &mut counts);
+        }
+        Ok(InputGenerator {
+            stub,
+            ranges: HashMap::new(),
+            charsets: HashMap::new(),
+            counts,
+        })
+    }
+
+    /// Names of the variables read by the stub, in the order they are first
+    /// read.
+    pub fn variables(&self) -> Vec<&str> {
+        let mut variables: Vec<&str> = Vec::new();
+        for cmd in &self.stub.commands {
+            for var in read_variables(cmd) {
+                if !variables.contains(&var.ident.as_str()) {
+                    variables.push(&var.ident);
+                }
+            }
+        }
+        variables
+    }
+
+    /// Limit the values (or the length of words and strings) of `variable`
+    /// to `range`.
+    pub fn set_range(&mut self, variable: &str, range: ValueRange) -> Result<()> {
+        let variables = self.variables();
+        if !variables.contains(&variable) {
+            return Err(anyhow!("Unknown variable {variable} (the stub reads: {})", variables.join(", ")))
+        }
+        if !range.is_valid() {
+            return Err(anyhow!("Invalid range {range} for {variable}"))
+        }
+        self.ranges.insert(variable.to_string(), range);
+        Ok(())
+    }
+
+    /// Use the bounds extracted from the constraints of a clash (see
+    /// [crate::clash::parse_constraints]) for the variables the stub reads.
+    /// Strict bounds are converted to inclusive ones, a missing bound keeps
+    /// its default value. Bounds that don't make sense are ignored.
+    pub fn apply_constraints(&mut self, constraints: &[VariableConstraints]) {
+        let variables: Vec<VariableCommand> =
+            self.stub.commands.iter().flat_map(read_variables).cloned().collect();
+        for constraint in constraints {
+            let Some(var) = variables.iter().find(|var| var.ident == constraint.variable) else {
+                continue
+            };
+            let default = self.range(var);
+            let (bounds, step) = match var.var_type {
+                VarType::Word | VarType::String => (constraint.length, 1.0),
+                // Floats are printed with at most 4 decimals
+                VarType::Float => (constraint.value, 0.0001),
+                VarType::Int | VarType::Long | VarType::Bool => (constraint.value, 1.0),
+            };
+            let inclusive = |bound: Bound, step: f64| match bound.inclusive {
+                true => bound.value,
+                false => bound.value + step,
+            };
+            let mut min = bounds.min.map_or(default.min, |bound| inclusive(bound, step));
+            let mut max = bounds.max.map_or(default.max, |bound| inclusive(bound, -step));
+            if bounds.max.is_none() && max < min {
+                max = min;
+            } else if bounds.min.is_none() && min > max {
+                min = max;
+            }
+            if !bounds.is_empty() {
+                let _ = self.set_range(&var.ident, ValueRange::new(min, max));
+            }
+            if !constraint.charset.is_empty() {
+                self.charsets.insert(var.ident.clone(), constraint.charset.clone());
+            }
+        }
+    }
+
+    /// Generate one input. Lines are separated by newlines, there is no
+    /// newline after the last line (like in the testcases of clashes).
+    pub fn generate(&self, rng: &mut impl Rng) -> Result<String> {
+        Ok(self.generate_shrinkable(rng)?.input)
+    }
+
+    /// Generate one input that can be passed to [InputGenerator::shrink].
+    pub fn generate_shrinkable(&self, rng: &mut impl Rng) -> Result<GeneratedInput> {
+        self.run(&mut RandomChoices(rng))
+    }
+
+    /// Find a smaller input than `input` for which `is_interesting` still
+    /// returns true, for example the smallest input that makes a solution
+    /// fail. Inputs are made smaller by removing loop iterations, making
+    /// numbers closer to 0 and words shorter. The result still has the shape
+    /// described by the stub.
+    ///
+    /// `is_interesting` is called at most `max_attempts` times.
+    pub fn shrink(
+        &self,
+        input: &GeneratedInput,
+        max_attempts: usize,
+        mut is_interesting: impl FnMut(&str) -> bool,
+    ) -> Result<GeneratedInput> {
+        let mut smallest = input.clone();
+        let mut attempts = 0;
+        while attempts < max_attempts {
+            let mut smaller = None;
+            for choices in shrink_candidates(&smallest.choices) {
+                let candidate = self.run(&mut ReplayedChoices(choices.iter()))?;
+                if !candidate.is_smaller_than(&smallest) {
+                    continue
+                }
+                if attempts == max_attempts {
+                    break
+                }
+                attempts += 1;
+                if is_interesting(&candidate.input) {
+                    smaller = Some(candidate);
+                    break
+                }
+            }
+            match smaller {
+                Some(smaller) => smallest = smaller,
+                None => break,
+            }
+        }
+        Ok(smallest)
+    }
+
+    fn run(&self, source: &mut dyn Choices) -> Result<GeneratedInput> {
+        let mut generation = Generation {
+            generator: self,
+            source,
+            choices: Vec::new(),
+            values: HashMap::new(),
+            lines: Vec::new(),
+        };
+        for cmd in &self.stub.commands {
+            generation.run(cmd)?;
+        }
+        Ok(GeneratedInput {
+            input: generation.lines.join("\n"),
+            choices: generation.choices,
+        })
+    }
+
+    fn range(&self, var: &VariableCommand) -> ValueRange {
+        if let Some(range) = self.ranges.get(&var.ident) {
+            return *range
+        }
+        match var.var_type {
+            _ if self.counts.contains(&var.ident) => ValueRange::new(1.0, 10.0),
+            VarType::Int | VarType::Float => ValueRange::new(0.0, 100.0),
+            VarType::Long => ValueRange::new(0.0, 1e9),
+            VarType::Bool => ValueRange::new(0.0, 1.0),
+            VarType::Word => ValueRange::new(1.0, 10.0),
+            VarType::String => ValueRange::new(1.0, 20.0),
+        }
+    }
+}
+
+fn collect_counts(cmd: &Cmd, counts: &mut BTreeSet<String>) {
+    match cmd {
+        Cmd::Loop { count_var, command } => {
+            counts.insert(count_var.clone());
+            collect_counts(command, counts);
+        }
+        Cmd::LoopLine { count_var, .. } => {
+            counts.insert(count_var.clone());
+        }
+        _ => {}
+    }
+    for var in read_variables(cmd) {
+        if let Some(max_length) = &var.max_length {
+            counts.insert(max_length.clone());
+        }
+    }
+}
+
+fn read_variables(cmd: &Cmd) -> Vec<&VariableCommand> {
+    match cmd {
+        Cmd::Read(variables) | Cmd::LoopLine { variables, .. } => variables.iter().collect(),
+        Cmd::Loop { command, .. } => read_variables(command),
+        _ => Vec::new(),
+    }
+}
+
+/// One random decision made while generating an input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Choice {
+    Int(i64),
+    Float(f64),
+}
+
+impl Choice {
+    fn zero(&self) -> Choice {
+        match self {
+            Choice::Int(_) => Choice::Int(0),
+            Choice::Float(_) => Choice::Float(0.0),
+        }
+    }
+}
+
+/// Where the choices of a [Generation] come from.
+trait Choices {
+    fn int(&mut self, min: i64, max: i64) -> i64;
+    fn float(&mut self, min: f64, max: f64) -> f64;
+}
+
+struct RandomChoices<'a, R>(&'a mut R);
+
+impl<R: Rng> Choices for RandomChoices<'_, R> {
+    fn int(&mut self, min: i64, max: i64) -> i64 {
+        self.0.gen_range(min..=max)
+    }
+
+    fn float(&mut self, min: f64, max: f64) -> f64 {
+        self.0.gen_range(min..=max)
+    }
+}
+
+/// Makes the same choices again. Choices that no longer fit their range are
+/// clamped, once they run out the simplest value (closest to 0) is chosen.
+struct ReplayedChoices<'a>(std::slice::Iter<'a, Choice>);
+
+impl Choices for ReplayedChoices<'_> {
+    fn int(&mut self, min: i64, max: i64) -> i64 {
+        match self.0.next() {
+            Some(Choice::Int(value)) => *value,
+            Some(Choice::Float(value)) => *value as i64,
+            None => 0,
+        }
+        .clamp(min, max)
+    }
+
+    fn float(&mut self, min: f64, max: f64) -> f64 {
+        match self.0.next() {
+            Some(Choice::Int(value)) => *value as f64,
+            Some(Choice::Float(value)) => *value,
+            None => 0.0,
+        }
+        .clamp(min, max)
+    }
+}
+
+struct Generation<'a> {
+    generator: &'a InputGenerator,
+    source: &'a mut dyn Choices,
+    // The choices that were made, for replaying the generation
+    choices: Vec<Choice>,
+    // Integer values that have been read so far, for loop counts and lengths
+    values: HashMap<String, i64>,
+    lines: Vec<String>,
+}
+
+impl Generation<'_> {
+    fn run(&mut self, cmd: &Cmd) -> Result<()> {
+        match cmd {
+            Cmd::Read(variables) => {
+                let line = self.values_of(variables)?;
+                self.lines.push(line.join(" "));
+            }
+            Cmd::Loop { count_var, command } => {
+                for _ in 0..self.count(count_var)? {
+                    self.run(command)?;
+                }
+            }
+            Cmd::LoopLine { count_var, variables } => {
+                let mut line = Vec::new();
+                for _ in 0..self.count(count_var)? {
+                    line.extend(self.values_of(variables)?);
+                }
+                self.lines.push(line.join(" "));
+            }
+            Cmd::Write { .. } | Cmd::WriteJoin { .. } | Cmd::External(_) => {}
+        }
+        Ok(())
+    }
+
+    fn values_of(&mut self, variables: &[VariableCommand]) -> Result<Vec<String>> {
+        variables.iter().map(|var| self.value(var)).collect()
+    }
+
+    fn value(&mut self, var: &VariableCommand) -> Result<String> {
+        let range = self.generator.range(var);
+        let value = match var.var_type {
+            VarType::Int | VarType::Long | VarType::Bool => {
+                let value = self
+                    .integer(range)
+                    .with_context(|| format!("No integers in range of {}", var.ident))?;
+                self.values.insert(var.ident.clone(), value);
+                value.to_string()
+            }
+            VarType::Float => {
+                // Rounded before it is recorded so replaying gives the same input
+                let value = format_float(self.source.float(range.min, range.max));
+                self.choices
+                    .push(Choice::Float(value.parse().expect("formatted float should parse")));
+                value
+            }
+            VarType::Word | VarType::String => {
+                let mut range = range;
+                if let Some(max_length) = &var.max_length {
+                    let max_length = self.count(max_length)? as f64;
+                    range.max = range.max.min(max_length);
+                    range.min = range.min.min(range.max);
+                }
+                let length = self.integer(range).unwrap_or(0).max(0) as usize;
+                self.text(length, var)
+            }
+        };
+        Ok(value)
+    }
+
+    fn integer(&mut self, range: ValueRange) -> Option<i64> {
+        let (min, max) = (range.min.ceil() as i64, range.max.floor() as i64);
+        (min <= max).then(|| self.choose(min, max))
+    }
+
+    fn choose(&mut self, min: i64, max: i64) -> i64 {
+        let value = self.source.int(min, max);
+        self.choices.push(Choice::Int(value));
+        value
+    }
+
+    // Loop counts and lengths are either numbers or variables read earlier
+    fn count(&self, count: &str) -> Result<i64> {
+        if let Ok(number) = count.parse() {
+            return Ok(number)
+        }
+        self.values
+            .get(count)
+            .copied()
+            .with_context(|| format!("{count} is used as a count before it is read"))
+    }
+
+    // Strings may contain single spaces, but not at the ends
+    fn text(&mut self, length: usize, var: &VariableCommand) -> String {
+        let (chars, spaces) = match self.generator.charsets.get(&var.ident) {
+            Some(charset) => {
+                let chars: Vec<char> = charset
+                    .iter()
+                    .filter(|class| **class != CharClass::Spaces)
+                    .flat_map(CharClass::chars)
+                    .collect();
+                (chars, charset.contains(&CharClass::Spaces))
+            }
+            None => (CharClass::Lowercase.chars(), true),
+        };
+        // Only spaces are not enough for a word
+        let chars = if chars.is_empty() {
+            CharClass::Lowercase.chars()
+        } else {
+            chars
+        };
+        let spaces = spaces && var.var_type == VarType::String;
+
+        let mut text = String::with_capacity(length);
+        for i in 0..length {
+            let inside = i > 0 && i + 1 < length && !text.ends_with(' ');
+            if spaces && inside && self.choose(0, 5) == 5 {
+                text.push(' ');
+            } else {
+                text.push(chars[self.choose(0, chars.len() as i64 - 1) as usize]);
+            }
+        }
+        text
+    }
+}
+
+/// Variants of `choices` that may generate a smaller input: with runs of
+/// choices set to 0, with single choices closer to 0, with a count decreased
+/// along with the choices of one of the iterations removed and with runs of
+/// choices removed. Loop counts are chosen before the values inside the
+/// loops, so the first candidates tend to shrink the input the most.
+fn shrink_candidates(choices: &[Choice]) -> impl Iterator<Item = Vec<Choice>> + '_ {
+    // Zeroing many choices at once is a lot faster than one at a time
+    let zeroed_runs = [64, 16, 4].into_iter().flat_map(move |size| {
+        (0..choices.len()).step_by(size).filter_map(move |start| {
+            let end = (start + size).min(choices.len());
+            if choices[start..end].iter().all(|choice| *choice == choice.zero()) {
+                return None
+            }
+            let mut candidate = choices.to_vec();
+            for choice in &mut candidate[start..end] {
+                *choice = choice.zero();
+            }
+            Some(candidate)
+        })
+    });
+    let deletions = [8, 4, 2, 1].into_iter().flat_map(move |size| {
+        (0..choices.len().saturating_sub(size - 1)).rev().map(move |start| {
+            let mut candidate = choices.to_vec();
+            candidate.drain(start..start + size);
+            candidate
+        })
+    });
+    // Fewer iterations of a loop need the choices of an iteration removed too
+    let fewer_iterations = (0..choices.len()).flat_map(move |i| {
+        (1..=8).filter(move |size| i + size < choices.len()).filter_map(move |size| {
+            let Choice::Int(count) = choices[i] else {
+                return None
+            };
+            let mut candidate = choices.to_vec();
+            candidate[i] = Choice::Int(count - count.signum());
+            candidate.drain(i + 1..i + 1 + size);
+            Some(candidate)
+        })
+    });
+    let simplifications = (0..choices.len()).flat_map(move |i| {
+        let simpler = match choices[i] {
+            Choice::Int(value) => [
+                Choice::Int(0),
+                Choice::Int(value / 2),
+                Choice::Int(value - value.signum()),
+            ],
+            Choice::Float(value) => [
+                Choice::Float(0.0),
+                Choice::Float(value.trunc()),
+                Choice::Float(value / 2.0),
+            ],
+        };
+        simpler.into_iter().filter(move |choice| *choice != choices[i]).map(move |choice| {
+            let mut candidate = choices.to_vec();
+            candidate[i] = choice;
+            candidate
+        })
+    });
+    zeroed_runs.chain(simplifications).chain(fewer_iterations).chain(deletions)
+}
+
+// At most 4 decimals without trailing zeros
+fn format_float(value: f64) -> String {
+    let formatted = format!("{value:.4}");
+    formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+impl FromStr for ValueRange {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (min, max) = s
+            .split_once("..")
+            .with_context(|| format!("Invalid range '{s}' (expected MIN..MAX)"))?;
+        let parse = |bound: &str| {
+            bound
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|bound| bound.is_finite())
+                .with_context(|| format!("Invalid bound '{bound}' in range '{s}'"))
+        };
+        let range = ValueRange::new(parse(min)?, parse(max)?);
+        if range.min > range.max {
+            return Err(anyhow!("Invalid range '{s}' (MIN is larger than MAX)"))
+        }
+        if !range.is_valid() {
+            return Err(anyhow!("Invalid range '{s}' (too wide)"))
+        }
+        Ok(range)
+    }
+}
+
+impl std::fmt::Display for ValueRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..{}", self.min, self.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn generate(generator: &InputGenerator, seed: u64) -> String {
+        generator.generate(&mut StdRng::seed_from_u64(seed)).unwrap()
+    }
+
+    #[test]
+    fn test_input_follows_stub() {
+        let stub = "read n:int s:word(3)\nloop n read x:int b:bool\nloopline n f:float\nwrite answer";
+        let mut generator = InputGenerator::new(stub).unwrap();
+        generator.set_range("x", ValueRange::new(-5.0, 5.0)).unwrap();
+
+        for seed in 0..20 {
+            let input = generate(&generator, seed);
+            let lines: Vec<&str> = input.lines().collect();
+            let (n, s) = lines[0].split_once(' ').unwrap();
+            let n: usize = n.parse().unwrap();
+            assert!((1..=10).contains(&n), "loop counts should default to 1..10");
+            assert!((1..=3).contains(&s.len()) && s.chars().all(|c| c.is_ascii_lowercase()));
+            assert_eq!(lines.len(), n + 2);
+            for line in &lines[1..=n] {
+                let (x, b) = line.split_once(' ').unwrap();
+                assert!((-5..=5).contains(&x.parse::<i32>().unwrap()));
+                assert!(b == "0" || b == "1");
+            }
+            assert_eq!(lines[n + 1].split(' ').count(), n);
+            assert!(lines[n + 1].split(' ').all(|f| f.parse::<f64>().is_ok()));
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_input() {
+        let generator = InputGenerator::new("read n:int\nloop n read line:string(50)").unwrap();
+        assert_eq!(generate(&generator, 7), generate(&generator, 7));
+        let string_lines = generate(&generator, 7);
+        for line in string_lines.lines().skip(1) {
+            assert_eq!(line, line.trim());
+            assert!(!line.contains("  "));
+        }
+    }
+
+    #[test]
+    fn test_lengths_from_variables() {
+        let mut generator = InputGenerator::new("read len:int\nread w:word(len)").unwrap();
+        generator.set_range("len", ValueRange::new(4.0, 4.0)).unwrap();
+        generator.set_range("w", ValueRange::new(1.0, 100.0)).unwrap();
+        for seed in 0..10 {
+            let input = generate(&generator, seed);
+            assert!(input.lines().nth(1).unwrap().len() <= 4);
+        }
+    }
+
+    #[test]
+    fn test_ranges_from_constraints() {
+        let mut generator = InputGenerator::new("read n:int\nloop n read code:word(10) x:float").unwrap();
+        let constraints = crate::clash::parse_constraints(
+            "2 < [[n]] <= 3\n[[code]] has exactly 5 uppercase letters\n0 < [[x]] < 1\n1 ≤ [[unused]] ≤ 2",
+        );
+        generator.apply_constraints(&constraints);
+        for seed in 0..10 {
+            let input = generate(&generator, seed);
+            let mut lines = input.lines();
+            assert_eq!(lines.next(), Some("3"));
+            for line in lines {
+                let (code, x) = line.split_once(' ').unwrap();
+                assert!(code.len() == 5 && code.chars().all(|ch| ch.is_ascii_uppercase()), "{code}");
+                let x: f64 = x.parse().unwrap();
+                assert!(0.0 < x && x < 1.0, "{x}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_shrink_to_minimal_counterexample() {
+        let generator = InputGenerator::new("read n:int\nloop n read x:int name:word(8)").unwrap();
+        // Pretend that a solution fails whenever some x is at least 50
+        let fails = |input: &str| {
+            input
+                .lines()
+                .skip(1)
+                .any(|line| line.split(' ').next().unwrap().parse::<i32>().unwrap() >= 50)
+        };
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let failing = std::iter::repeat_with(|| generator.generate_shrinkable(&mut rng).unwrap())
+            .find(|generated| fails(&generated.input))
+            .unwrap();
+        let shrunk = generator.shrink(&failing, 10_000, fails).unwrap();
+        assert_eq!(shrunk.input, "1\n50 a");
+    }
+
+    #[test]
+    fn test_shrink_gives_up_after_max_attempts() {
+        let generator = InputGenerator::new("read n:int\nloop n read x:int").unwrap();
+        let generated = generator.generate_shrinkable(&mut StdRng::seed_from_u64(3)).unwrap();
+        let mut attempts = 0;
+        let shrunk = generator
+            .shrink(&generated, 5, |_| {
+                attempts += 1;
+                false
+            })
+            .unwrap();
+        assert_eq!(attempts, 5);
+        assert_eq!(shrunk, generated);
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        let mut generator = InputGenerator::new("read n:int").unwrap();
+        assert!(generator.set_range("m", ValueRange::new(1.0, 2.0)).is_err());
+        assert!(generator.set_range("n", ValueRange::new(2.0, 1.0)).is_err());
+        assert!("1..".parse::<ValueRange>().is_err());
+        assert!("5..1".parse::<ValueRange>().is_err());
+        assert!("0..inf".parse::<ValueRange>().is_err());
+        assert!("-1e308..1e308".parse::<ValueRange>().is_err());
+        let mut generator = InputGenerator::new("read x:float").unwrap();
+        assert!(generator.set_range("x", ValueRange::new(0.0, f64::INFINITY)).is_err());
+        assert!(generator.set_range("x", ValueRange::new(-1e308, 1e308)).is_err());
+        assert!(generator.set_range("x", ValueRange::new(f64::NAN, 1.0)).is_err());
+        assert!(generator.set_range("x", ValueRange::new(-1e300, 1e300)).is_ok());
+        generate(&generator, 1);
+        assert_eq!("-3..2.5".parse::<ValueRange>().unwrap(), ValueRange::new(-3.0, 2.5));
+    }
+}